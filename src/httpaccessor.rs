@@ -1,37 +1,216 @@
 use tokio::time::Duration;
-use futures::{future, StreamExt, Future};
-use futures::task::{Context, Poll};
-use tokio::macros::support::Pin;
+use futures::{StreamExt, Future};
+use std::sync::Arc;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use once_cell::sync::OnceCell;
+use crate::httpaccessor_upstream::HttpAccessorUpstream;
+use crate::httpaccessor_transport::{AccessorTransport, HttpTransport, HttpTransportErrorKind, ReqwestTransport};
+use crate::httpaccessor_cassette::HttpCassetteTransport;
+use crate::httpaccessor_tracing::{HttpTraceContext, HttpTracePropagation};
+use crate::httpaccessor_ratelimit::HttpRateLimiter;
+use crate::httpaccessor_compression::HttpCompression;
+#[cfg(all(unix, feature="http_async"))]
+use crate::httpaccessor_unix::UnixSocketTransport;
+use crate::httpaccessor_hyper::{HttpProtocol, HttpProtocolOptions, HyperTransport};
+use crate::httpaccessor_resolver::{HttpResolver, HttpResolverService};
+use tracing::Instrument;
 
 #[derive(Debug)]
 pub struct HttpAccessorError {
-    err_type: HttpAccessorErrorType,
-    msg: String
+    pub err_type: HttpAccessorErrorType,
+    pub(crate) msg: String
 }
 
 #[derive(Debug)]
-enum HttpAccessorErrorType {
+pub enum HttpAccessorErrorType {
     OpenUrlError(reqwest::Error),
     GetContentError(reqwest::Error),
-    GetMultiContentError(reqwest::Error)
+    GetMultiContentError(reqwest::Error),
+    BuildClientError(reqwest::Error),
+    TransportError(HttpTransportErrorKind),
+    CassetteIoError(std::io::Error),
+    CassetteFormatError(serde_json::Error),
+    CompressError(std::io::Error),
+    NoUpstreamInstance
 }
 
+impl HttpAccessorError {
+    pub fn transport(kind: HttpTransportErrorKind, msg: &str) -> Self {
+        Self {
+            err_type: HttpAccessorErrorType::TransportError(kind),
+            msg: msg.to_string()
+        }
+    }
+
+    pub fn msg(&self) -> &str {
+        self.msg.as_str()
+    }
+
+    pub(crate) fn is_connect_error(&self) -> bool {
+        match &self.err_type {
+            HttpAccessorErrorType::OpenUrlError(e) => e.is_connect(),
+            HttpAccessorErrorType::TransportError(kind) => *kind == HttpTransportErrorKind::Connect,
+            _ => false
+        }
+    }
+
+    pub(crate) fn kind_name(&self) -> &'static str {
+        match &self.err_type {
+            HttpAccessorErrorType::OpenUrlError(e) if e.is_connect() => "connect",
+            HttpAccessorErrorType::OpenUrlError(e) if e.is_timeout() => "timeout",
+            HttpAccessorErrorType::OpenUrlError(_) => "request",
+            HttpAccessorErrorType::GetContentError(_) | HttpAccessorErrorType::GetMultiContentError(_) => "content",
+            HttpAccessorErrorType::BuildClientError(_) => "build_client",
+            HttpAccessorErrorType::TransportError(HttpTransportErrorKind::Connect) => "connect",
+            HttpAccessorErrorType::TransportError(HttpTransportErrorKind::Timeout) => "timeout",
+            HttpAccessorErrorType::TransportError(HttpTransportErrorKind::Other) => "transport",
+            HttpAccessorErrorType::CassetteIoError(_) | HttpAccessorErrorType::CassetteFormatError(_) => "cassette",
+            HttpAccessorErrorType::CompressError(_) => "compress",
+            HttpAccessorErrorType::NoUpstreamInstance => "no_upstream"
+        }
+    }
+
+    pub(crate) fn is_timeout_error(&self) -> bool {
+        match &self.err_type {
+            HttpAccessorErrorType::OpenUrlError(e) => e.is_timeout(),
+            HttpAccessorErrorType::TransportError(kind) => *kind == HttpTransportErrorKind::Timeout,
+            _ => false
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpAccessorRequest {
+    pub(crate) method: reqwest::Method,
+    pub(crate) url: String,
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) body: Option<Vec<u8>>,
+    pub(crate) timeout: Option<u32>,
+    pub(crate) compression: Option<HttpCompression>,
+    pub(crate) retryable: Option<bool>,
+    pub(crate) trace_parent: Option<HttpTraceContext>
+}
+
+impl HttpAccessorRequest {
+    pub fn new(method: reqwest::Method, url: &str) -> Self {
+        Self {
+            method,
+            url: url.to_string(),
+            headers: Vec::new(),
+            body: None,
+            timeout: None,
+            compression: None,
+            retryable: None,
+            trace_parent: None
+        }
+    }
+
+    pub fn get(url: &str) -> Self {
+        Self::new(reqwest::Method::GET, url)
+    }
+
+    pub fn post(url: &str, body: &str) -> Self {
+        Self::new(reqwest::Method::POST, url).body(body.as_bytes().to_vec())
+    }
+
+    pub fn header(mut self, key: &str, val: &str) -> Self {
+        self.headers.push((key.to_string(), val.to_string()));
+        self
+    }
+
+    pub fn body(mut self, body: Vec<u8>) -> Self {
+        self.body = Some(body);
+        self
+    }
+
+    pub fn timeout(mut self, timeout: u32) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn compression(mut self, compression: HttpCompression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    pub fn retryable(mut self, retryable: bool) -> Self {
+        self.retryable = Some(retryable);
+        self
+    }
+
+    pub fn trace_parent(mut self, trace_parent: HttpTraceContext) -> Self {
+        self.trace_parent = Some(trace_parent);
+        self
+    }
+
+    pub fn method(&self) -> &reqwest::Method {
+        &self.method
+    }
+    pub fn url(&self) -> &str {
+        self.url.as_str()
+    }
+    pub fn headers(&self) -> &[(String, String)] {
+        self.headers.as_slice()
+    }
+    pub fn header_value(&self, key: &str) -> Option<&str> {
+        self.headers.iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+    }
+    pub fn body_bytes(&self) -> Option<&[u8]> {
+        self.body.as_deref()
+    }
+    pub fn timeout_secs(&self) -> Option<u32> {
+        self.timeout
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct HttpAccessorResponse {
     pub(crate) url: String,
     pub(crate) status_code: u16,
+    pub(crate) headers: Vec<(String, String)>,
     pub(crate) content: String,
+    pub(crate) version: reqwest::Version
 }
 
 impl HttpAccessorResponse {
+    pub fn new(url: &str, status_code: u16, content: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            status_code,
+            headers: Vec::new(),
+            content: content.to_string(),
+            version: reqwest::Version::HTTP_11
+        }
+    }
+
+    pub fn header(mut self, key: &str, val: &str) -> Self {
+        self.headers.push((key.to_string(), val.to_string()));
+        self
+    }
+
     pub fn url(&self) -> &str {
         self.url.as_str()
     }
     pub fn status_code(&self) -> u16 {
         self.status_code
     }
+    pub fn headers(&self) -> &[(String, String)] {
+        self.headers.as_slice()
+    }
+    pub fn header_value(&self, key: &str) -> Option<&str> {
+        self.headers.iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+    }
     pub fn content(&self) -> &str {
         self.content.as_str()
     }
+    pub fn version(&self) -> reqwest::Version {
+        self.version
+    }
 }
 
 #[derive(Debug)]
@@ -53,32 +232,53 @@ impl HttpAccessorResponseError {
     }
 }
 
-struct HttpAccessorResponseInner {
-    status_code: u16,
-    content: String,
-}
-
+#[derive(Clone)]
 pub struct HttpAccessor {
-    client: Option<reqwest::Client>,
-    pub(crate) timeout: u32
+    transport: Option<Arc<dyn HttpTransport>>,
+    default_transport: Arc<OnceCell<Arc<dyn HttpTransport>>>,
+    pub(crate) timeout: u32,
+    pub(crate) retries: u32,
+    pub(crate) retry_interval: u64,
+    pub(crate) retry_idempotent: bool,
+    pub(crate) trace_propagation: Option<HttpTracePropagation>,
+    pub(crate) rate_limiter: Option<HttpRateLimiter>,
+    pub(crate) compression: Option<HttpCompression>,
+    pub(crate) compression_threshold: usize,
+    pub(crate) upstream: Option<HttpAccessorUpstream>,
+    pub(crate) http_protocol: Option<HttpProtocol>,
+    pub(crate) http2_keep_alive_interval: Option<u32>,
+    pub(crate) http2_keep_alive_timeout: u32,
+    pub(crate) http2_keep_alive_while_idle: bool,
+    pub(crate) resolve_overrides: HashMap<String, Vec<IpAddr>>,
+    pub(crate) resolver: Option<Arc<dyn HttpResolver>>
 }
 
-struct DummyFutureError {
-}
-
-impl future::Future for DummyFutureError {
-    type Output = ();
-
-    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Self::Output> {
-        unimplemented!()
+impl Default for HttpAccessor {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl HttpAccessor {
     pub fn new() -> Self{
         Self {
-            client: None,
-            timeout: 60
+            transport: None,
+            default_transport: Arc::new(OnceCell::new()),
+            timeout: 60,
+            retries: 0,
+            retry_interval: 100,
+            retry_idempotent: false,
+            trace_propagation: None,
+            rate_limiter: None,
+            compression: None,
+            compression_threshold: 1024,
+            upstream: None,
+            http_protocol: None,
+            http2_keep_alive_interval: None,
+            http2_keep_alive_timeout: 20,
+            http2_keep_alive_while_idle: false,
+            resolve_overrides: HashMap::new(),
+            resolver: None
         }
     }
 
@@ -87,8 +287,147 @@ impl HttpAccessor {
         self
     }
 
-    async fn handle_async_resp(url: &str, resp: reqwest::Response) -> Result<HttpAccessorResponse, HttpAccessorResponseError> {
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    pub fn retry_interval(mut self, retry_interval: u64) -> Self {
+        self.retry_interval = retry_interval;
+        self
+    }
+
+    pub fn retry_idempotent(mut self, retry_idempotent: bool) -> Self {
+        self.retry_idempotent = retry_idempotent;
+        self
+    }
+
+    pub fn trace_propagation(mut self, trace_propagation: HttpTracePropagation) -> Self {
+        self.trace_propagation = Some(trace_propagation);
+        self
+    }
+
+    pub fn rate_limiter(mut self, rate_limiter: HttpRateLimiter) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    pub fn compression(mut self, compression: HttpCompression, compression_threshold: usize) -> Self {
+        self.compression = Some(compression);
+        self.compression_threshold = compression_threshold;
+        self
+    }
+
+    pub fn upstream(mut self, upstream: HttpAccessorUpstream) -> Self {
+        self.upstream = Some(upstream);
+        self
+    }
+
+    /// `Http1Only` keeps the default reqwest client, `Http2Alpn` switches reqwest to rustls (native roots) so h2 can be
+    /// negotiated, `Http2PriorKnowledge` speaks h2c to cleartext backends.
+    pub fn http_protocol(mut self, http_protocol: HttpProtocol) -> Self {
+        self.http_protocol = Some(http_protocol);
+        self.default_transport = Arc::new(OnceCell::new());
+        self
+    }
+
+    /// reqwest 0.10 has no HTTP/2 ping settings, so keep-alive only applies with `HttpProtocol::Http2PriorKnowledge`,
+    /// where requests then go through a bare hyper h2c client: no redirects, proxies or default headers.
+    pub fn http2_keep_alive(mut self, interval: u32, timeout: u32, while_idle: bool) -> Self {
+        self.http2_keep_alive_interval = Some(interval);
+        self.http2_keep_alive_timeout = timeout;
+        self.http2_keep_alive_while_idle = while_idle;
+        self.default_transport = Arc::new(OnceCell::new());
+        self
+    }
+
+    /// reqwest 0.10 has no resolver hook, so with `resolve` or `resolver` set requests go through a hyper + rustls
+    /// client (native roots): HTTP/1.1 unless `http_protocol` says otherwise, but no redirects, proxies,
+    /// native-tls or reqwest default headers.
+    pub fn resolve(mut self, host: &str, addr: IpAddr) -> Self {
+        self.resolve_overrides.entry(host.to_lowercase()).or_default().push(addr);
+        self.default_transport = Arc::new(OnceCell::new());
+        self
+    }
+
+    /// Same transport caveats as `resolve`.
+    pub fn resolver<R: HttpResolver + 'static>(mut self, resolver: R) -> Self {
+        self.resolver = Some(Arc::new(resolver));
+        self.default_transport = Arc::new(OnceCell::new());
+        self
+    }
+
+    pub fn transport<T: HttpTransport + 'static>(mut self, transport: T) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Records through the transport this accessor would use, built from the protocol, resolver and
+    /// transport settings made before this call, unless the cassette was given its own `inner`.
+    pub fn cassette(self, cassette: HttpCassetteTransport) -> Self {
+        let inner = AccessorTransport::new(self.clone());
+        self.transport(cassette.or_inner(Arc::new(inner)))
+    }
+
+    #[cfg(all(unix, feature="http_async"))]
+    pub fn unix_socket(self, socket_path: &str) -> Self {
+        self.transport(UnixSocketTransport::new(socket_path))
+    }
+
+    fn hyper_transport(&self) -> Option<HyperTransport> {
+        let h2c_keep_alive = self.http_protocol == Some(HttpProtocol::Http2PriorKnowledge) && self.http2_keep_alive_interval.is_some();
+        if !h2c_keep_alive && self.resolve_overrides.is_empty() && self.resolver.is_none() {
+            if self.http2_keep_alive_interval.is_some() {
+                log::warn!("http2 keep-alive is only supported with HttpProtocol::Http2PriorKnowledge, ignored");
+            }
+            return None;
+        }
+        let options = HttpProtocolOptions {
+            protocol: self.http_protocol.unwrap_or(HttpProtocol::Http1Only),
+            http2_keep_alive_interval: self.http2_keep_alive_interval,
+            http2_keep_alive_timeout: self.http2_keep_alive_timeout,
+            http2_keep_alive_while_idle: self.http2_keep_alive_while_idle
+        };
+        Some(HyperTransport::new(&options, HttpResolverService::new(self.resolve_overrides.clone(), self.resolver.clone())))
+    }
+
+    pub fn open_client(&mut self) -> Result<(), HttpAccessorError> {
+        self.get_transport()?;
+        Ok(())
+    }
+
+    pub(crate) fn get_transport(&self) -> Result<Arc<dyn HttpTransport>, HttpAccessorError> {
+        if let Some(transport) = self.transport.as_ref() {
+            return Ok(transport.clone());
+        }
+        self.default_transport.get_or_try_init(|| {
+            let transport: Arc<dyn HttpTransport> = match self.hyper_transport() {
+                Some(hyper_transport) => Arc::new(hyper_transport),
+                None => {
+                    let client_builder = match self.http_protocol {
+                        Some(HttpProtocol::Http2Alpn) => reqwest::Client::builder().use_rustls_tls(),
+                        Some(HttpProtocol::Http2PriorKnowledge) => reqwest::Client::builder().http2_prior_knowledge(),
+                        _ => reqwest::Client::builder()
+                    };
+                    let client = client_builder
+                        .build()
+                        .map_err(|e| HttpAccessorError {
+                            err_type: HttpAccessorErrorType::BuildClientError(e),
+                            msg: "build client fail".to_string()
+                        })?;
+                    Arc::new(ReqwestTransport::new(client))
+                }
+            };
+            Ok(transport)
+        }).cloned()
+    }
+
+    pub(crate) async fn handle_async_resp(url: &str, resp: reqwest::Response) -> Result<HttpAccessorResponse, HttpAccessorResponseError> {
         let status_code = resp.status();
+        let version = resp.version();
+        let headers: Vec<(String, String)> = resp.headers().iter()
+            .map(|(k, v)| (k.as_str().to_string(), String::from_utf8_lossy(v.as_bytes()).to_string()))
+            .collect();
         let server_info = resp.text().await.map_err(|e| HttpAccessorResponseError {
             url: url.to_string(),
             status_code: Some(status_code.as_u16()),
@@ -100,45 +439,20 @@ impl HttpAccessor {
         Ok(HttpAccessorResponse{
             url: url.to_string(),
             status_code: status_code.as_u16(),
-            content: server_info
+            headers,
+            content: server_info,
+            version
         })
     }
 
     pub async fn async_get(url: &str, timeout: u32) -> Result<HttpAccessorResponse, HttpAccessorResponseError> {
-        let resp = reqwest::Client::new()
-            .get(url)
-            .timeout(Duration::from_secs(timeout as u64))
-            .send()
-            .await
-            .map_err(|e| HttpAccessorResponseError {
-                url: url.to_string(),
-                status_code: None,
-                err: Some(HttpAccessorError {
-                    err_type: HttpAccessorErrorType::OpenUrlError(e),
-                    msg: "async get fail".to_string()
-                })
-            })?;
-        HttpAccessor::handle_async_resp(url, resp).await
+        HttpAccessor::new().execute(HttpAccessorRequest::get(url).timeout(timeout)).await
     }
 
     pub fn async_post<'a>(url: &'a str, body: &str, timeout: u32) -> impl Future<Output = Result<HttpAccessorResponse, HttpAccessorResponseError>> + 'a {
-        let request_builder = reqwest::Client::new()
-            .post(url)
-            .body(body.to_string())
-            .timeout(Duration::from_secs(timeout as u64));
+        let request = HttpAccessorRequest::post(url, body).timeout(timeout);
         async move {
-            let resp = request_builder
-                .send()
-                .await
-                .map_err(|e| HttpAccessorResponseError {
-                    url: url.to_string(),
-                    status_code: None,
-                    err: Some(HttpAccessorError {
-                        err_type: HttpAccessorErrorType::OpenUrlError(e),
-                        msg: "async post fail".to_string()
-                    })
-                })?;
-            HttpAccessor::handle_async_resp(url, resp).await
+            HttpAccessor::new().execute(request).await
         }
     }
 
@@ -165,4 +479,159 @@ impl HttpAccessor {
         let resp_wrapper_list = resp_future_list.await;
         Ok(resp_wrapper_list)
     }
+
+    async fn send_url(&self, request: HttpAccessorRequest) -> Result<HttpAccessorResponse, HttpAccessorResponseError> {
+        let host = reqwest::Url::parse(request.url())
+            .ok()
+            .and_then(|url| url.host_str().map(|host| host.to_string()))
+            .unwrap_or_default();
+        let span = tracing::info_span!(
+            "http_request",
+            http.method = %request.method(),
+            http.host = %host,
+            http.status_code = tracing::field::Empty,
+            error.kind = tracing::field::Empty,
+            trace_id = tracing::field::Empty
+        );
+        if let Some(trace_context) = HttpTraceContext::from_headers(request.headers()) {
+            span.record("trace_id", &trace_context.trace_id.as_str());
+            trace_context.attach(&span);
+        }
+        if let Some(rate_limiter) = self.rate_limiter.as_ref() {
+            rate_limiter.acquire(host.as_str()).await;
+        }
+        let transport = self.get_transport().map_err(|e| HttpAccessorResponseError {
+            url: request.url.clone(),
+            status_code: None,
+            err: Some(e)
+        })?;
+        let rst = transport.send(request).instrument(span.clone()).await;
+        match &rst {
+            Ok(resp) => {
+                span.record("http.status_code", &resp.status_code);
+                if let Some(rate_limiter) = self.rate_limiter.as_ref() {
+                    rate_limiter.observe(host.as_str(), resp);
+                }
+            },
+            Err(e) => {
+                if let Some(status_code) = e.status_code {
+                    span.record("http.status_code", &status_code);
+                }
+                span.record("error.kind", &e.err.as_ref().map_or("unknown", |err| err.kind_name()));
+            }
+        }
+        rst
+    }
+
+    fn compress_body(&self, request: &mut HttpAccessorRequest) -> Result<(), HttpAccessorResponseError> {
+        if request.header_value("content-encoding").is_some() {
+            return Ok(());
+        }
+        let compression = match (request.compression, self.compression, request.body.as_ref()) {
+            (Some(compression), _, Some(_)) => compression,
+            (None, Some(compression), Some(body)) if body.len() >= self.compression_threshold => compression,
+            _ => return Ok(())
+        };
+        let compressed = compression.compress(request.body.as_ref().unwrap()).map_err(|e| HttpAccessorResponseError {
+            url: request.url.clone(),
+            status_code: None,
+            err: Some(HttpAccessorError {
+                err_type: HttpAccessorErrorType::CompressError(e),
+                msg: "compress request body fail".to_string()
+            })
+        })?;
+        request.body = Some(compressed);
+        request.headers.push(("Content-Encoding".to_string(), compression.content_encoding().to_string()));
+        Ok(())
+    }
+
+    fn is_retryable(&self, request: &HttpAccessorRequest, rst: &Result<HttpAccessorResponse, HttpAccessorResponseError>) -> bool {
+        let is_idempotent = matches!(request.method,
+            reqwest::Method::GET | reqwest::Method::HEAD | reqwest::Method::OPTIONS
+            | reqwest::Method::PUT | reqwest::Method::DELETE | reqwest::Method::TRACE);
+        let retry_transient = request.retryable.unwrap_or(self.retry_idempotent && is_idempotent);
+        match rst {
+            Ok(resp) => retry_transient && (resp.status_code == 502 || resp.status_code == 503 || resp.status_code == 504),
+            Err(e) => e.err.as_ref().is_some_and(|err| err.is_connect_error() || (retry_transient && err.is_timeout_error()))
+        }
+    }
+
+    pub async fn execute(&self, mut request: HttpAccessorRequest) -> Result<HttpAccessorResponse, HttpAccessorResponseError> {
+        if request.timeout.is_none() {
+            request.timeout = Some(self.timeout);
+        }
+        self.compress_body(&mut request)?;
+        if let Some(trace_propagation) = self.trace_propagation {
+            let trace_context = match request.trace_parent.take().or_else(HttpTraceContext::current) {
+                Some(trace_context) => trace_context.child(),
+                None => HttpTraceContext::new_root()
+            };
+            trace_context.inject(trace_propagation, &mut request);
+        }
+        let mut attempt = 0;
+        loop {
+            let rst = self.execute_once(request.clone()).await;
+            if attempt >= self.retries || !self.is_retryable(&request, &rst) {
+                return rst;
+            }
+            attempt += 1;
+            tokio::time::delay_for(Duration::from_millis(self.retry_interval * attempt as u64)).await;
+        }
+    }
+
+    async fn execute_once(&self, request: HttpAccessorRequest) -> Result<HttpAccessorResponse, HttpAccessorResponseError> {
+        let upstream = match self.upstream.as_ref() {
+            Some(upstream) => upstream,
+            None => return self.send_url(request).await
+        };
+        let path = request.url.clone();
+        let mut last_err = None;
+        for idx in upstream.candidates(path.as_str()) {
+            let mut instance_request = request.clone();
+            instance_request.url = upstream.join_url(idx, path.as_str());
+            let _outstanding_guard = upstream.acquire(idx);
+            match self.send_url(instance_request).await {
+                Ok(resp) => {
+                    if resp.status_code >= 500 {
+                        upstream.report_failure(idx);
+                    } else {
+                        upstream.report_success(idx);
+                    }
+                    return Ok(resp);
+                },
+                Err(e) => {
+                    upstream.report_failure(idx);
+                    let is_connect_error = e.err.as_ref().is_some_and(|err| err.is_connect_error());
+                    if !is_connect_error {
+                        return Err(e);
+                    }
+                    last_err = Some(e);
+                }
+            }
+        }
+        Err(last_err.unwrap_or(HttpAccessorResponseError {
+            url: path,
+            status_code: None,
+            err: Some(HttpAccessorError {
+                err_type: HttpAccessorErrorType::NoUpstreamInstance,
+                msg: "no upstream instance".to_string()
+            })
+        }))
+    }
+
+    pub async fn get(&self, path: &str) -> Result<HttpAccessorResponse, HttpAccessorResponseError> {
+        self.execute(HttpAccessorRequest::get(path)).await
+    }
+
+    pub async fn post(&self, path: &str, body: &str) -> Result<HttpAccessorResponse, HttpAccessorResponseError> {
+        self.execute(HttpAccessorRequest::post(path, body)).await
+    }
+
+    pub async fn multi_get(&self, paths: &[&str]) -> Result<Vec<Result<HttpAccessorResponse, HttpAccessorResponseError>>, HttpAccessorError> {
+        let resp_future_list = futures::stream::iter(
+            paths.iter().map(|path| self.get(path))
+        ).buffered(128).collect::<Vec<Result<HttpAccessorResponse, HttpAccessorResponseError>>>();
+        let resp_wrapper_list = resp_future_list.await;
+        Ok(resp_wrapper_list)
+    }
 }
@@ -0,0 +1,220 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use crate::httpaccessor::{HttpAccessorError, HttpAccessorErrorType, HttpAccessorRequest, HttpAccessorResponse, HttpAccessorResponseError};
+use crate::httpaccessor_transport::{HttpTransport, HttpTransportErrorKind};
+
+const REDACTED: &str = "REDACTED";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HttpCassetteMode {
+    Record,
+    Replay
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum HttpCassetteBodyEncoding {
+    Base64
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct HttpCassetteRequest {
+    method: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body_encoding: Option<HttpCassetteBodyEncoding>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct HttpCassetteResponse {
+    status_code: u16,
+    headers: Vec<(String, String)>,
+    content: String
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct HttpCassetteInteraction {
+    request: HttpCassetteRequest,
+    response: HttpCassetteResponse
+}
+
+struct HttpCassetteState {
+    interactions: Vec<HttpCassetteInteraction>,
+    used: Vec<bool>
+}
+
+pub struct HttpCassetteTransport {
+    path: PathBuf,
+    mode: HttpCassetteMode,
+    inner: Option<Arc<dyn HttpTransport>>,
+    match_body: bool,
+    redact_headers: Vec<String>,
+    state: Mutex<HttpCassetteState>
+}
+
+fn encode_body(body: Option<&[u8]>) -> (Option<String>, Option<HttpCassetteBodyEncoding>) {
+    match body.map(std::str::from_utf8) {
+        None => (None, None),
+        Some(Ok(body)) => (Some(body.to_string()), None),
+        Some(Err(_)) => (body.map(base64::encode), Some(HttpCassetteBodyEncoding::Base64))
+    }
+}
+
+impl HttpCassetteTransport {
+    fn with_mode(path: &str, mode: HttpCassetteMode, interactions: Vec<HttpCassetteInteraction>) -> Self {
+        let used = vec![false; interactions.len()];
+        Self {
+            path: PathBuf::from(path),
+            mode,
+            inner: None,
+            match_body: true,
+            redact_headers: vec![
+                "authorization".to_string(),
+                "proxy-authorization".to_string(),
+                "cookie".to_string(),
+                "set-cookie".to_string(),
+                "x-api-key".to_string()
+            ],
+            state: Mutex::new(HttpCassetteState {
+                interactions,
+                used
+            })
+        }
+    }
+
+    pub fn record(path: &str) -> Self {
+        Self::with_mode(path, HttpCassetteMode::Record, Vec::new())
+    }
+
+    pub fn replay(path: &str) -> Result<Self, HttpAccessorError> {
+        let content = std::fs::read_to_string(path).map_err(|e| HttpAccessorError {
+            err_type: HttpAccessorErrorType::CassetteIoError(e),
+            msg: format!("read cassette {} fail", path)
+        })?;
+        let interactions: Vec<HttpCassetteInteraction> = serde_json::from_str(content.as_str()).map_err(|e| HttpAccessorError {
+            err_type: HttpAccessorErrorType::CassetteFormatError(e),
+            msg: format!("parse cassette {} fail", path)
+        })?;
+        Ok(Self::with_mode(path, HttpCassetteMode::Replay, interactions))
+    }
+
+    pub fn inner<T: HttpTransport + 'static>(mut self, inner: T) -> Self {
+        self.inner = Some(Arc::new(inner));
+        self
+    }
+
+    pub(crate) fn or_inner(mut self, inner: Arc<dyn HttpTransport>) -> Self {
+        self.inner.get_or_insert(inner);
+        self
+    }
+
+    pub fn match_body(mut self, match_body: bool) -> Self {
+        self.match_body = match_body;
+        self
+    }
+
+    pub fn redact_header(mut self, key: &str) -> Self {
+        self.redact_headers.push(key.to_lowercase());
+        self
+    }
+
+    pub fn mode(&self) -> HttpCassetteMode {
+        self.mode
+    }
+
+    fn redact(&self, headers: &[(String, String)]) -> Vec<(String, String)> {
+        headers.iter().map(|(key, val)| {
+            match self.redact_headers.contains(&key.to_lowercase()) {
+                true => (key.clone(), REDACTED.to_string()),
+                false => (key.clone(), val.clone())
+            }
+        }).collect()
+    }
+
+    fn is_match(&self, recorded: &HttpCassetteRequest, request: &HttpAccessorRequest) -> bool {
+        if recorded.method != request.method().as_str() || recorded.url != request.url() {
+            return false;
+        }
+        let (body, body_encoding) = encode_body(request.body_bytes());
+        !self.match_body || (recorded.body == body && recorded.body_encoding == body_encoding)
+    }
+
+    fn find_interaction(&self, request: &HttpAccessorRequest) -> Option<HttpCassetteResponse> {
+        let mut state = self.state.lock().unwrap();
+        let HttpCassetteState { interactions, used } = &mut *state;
+        let matched: Vec<usize> = interactions.iter().enumerate()
+            .filter(|(_, interaction)| self.is_match(&interaction.request, request))
+            .map(|(idx, _)| idx)
+            .collect();
+        let idx = matched.iter().find(|idx| !used[**idx]).or_else(|| matched.last())?;
+        used[*idx] = true;
+        Some(interactions[*idx].response.clone())
+    }
+
+    fn append_interaction(&self, request: &HttpAccessorRequest, resp: &HttpAccessorResponse) -> Result<(), HttpAccessorError> {
+        let (body, body_encoding) = encode_body(request.body_bytes());
+        let mut state = self.state.lock().unwrap();
+        state.interactions.push(HttpCassetteInteraction {
+            request: HttpCassetteRequest {
+                method: request.method().as_str().to_string(),
+                url: request.url().to_string(),
+                headers: self.redact(request.headers()),
+                body,
+                body_encoding
+            },
+            response: HttpCassetteResponse {
+                status_code: resp.status_code(),
+                headers: self.redact(resp.headers()),
+                content: resp.content().to_string()
+            }
+        });
+        state.used.push(true);
+        let content = serde_json::to_string_pretty(&state.interactions).map_err(|e| HttpAccessorError {
+            err_type: HttpAccessorErrorType::CassetteFormatError(e),
+            msg: "serialize cassette fail".to_string()
+        })?;
+        std::fs::write(&self.path, content).map_err(|e| HttpAccessorError {
+            err_type: HttpAccessorErrorType::CassetteIoError(e),
+            msg: format!("write cassette {} fail", self.path.display())
+        })
+    }
+}
+
+impl HttpTransport for HttpCassetteTransport {
+    fn send(&self, request: HttpAccessorRequest) -> BoxFuture<'_, Result<HttpAccessorResponse, HttpAccessorResponseError>> {
+        Box::pin(async move {
+            match self.mode {
+                HttpCassetteMode::Replay => match self.find_interaction(&request) {
+                    Some(recorded) => {
+                        let mut resp = HttpAccessorResponse::new(request.url(), recorded.status_code, recorded.content.as_str());
+                        resp.headers = recorded.headers;
+                        Ok(resp)
+                    },
+                    None => Err(HttpAccessorResponseError {
+                        url: request.url().to_string(),
+                        status_code: None,
+                        err: Some(HttpAccessorError::transport(HttpTransportErrorKind::Other, "no cassette interaction matched"))
+                    })
+                },
+                HttpCassetteMode::Record => {
+                    let inner = self.inner.as_ref().ok_or_else(|| HttpAccessorResponseError {
+                        url: request.url().to_string(),
+                        status_code: None,
+                        err: Some(HttpAccessorError::transport(HttpTransportErrorKind::Other, "cassette has no inner transport, attach it with HttpAccessor::cassette"))
+                    })?;
+                    let resp = inner.send(request.clone()).await?;
+                    self.append_interaction(&request, &resp).map_err(|e| HttpAccessorResponseError {
+                        url: request.url().to_string(),
+                        status_code: Some(resp.status_code()),
+                        err: Some(e)
+                    })?;
+                    Ok(resp)
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,27 @@
+use std::io::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HttpCompression {
+    Gzip,
+    Zstd
+}
+
+impl HttpCompression {
+    pub fn content_encoding(&self) -> &'static str {
+        match self {
+            HttpCompression::Gzip => "gzip",
+            HttpCompression::Zstd => "zstd"
+        }
+    }
+
+    pub fn compress(&self, body: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+        match self {
+            HttpCompression::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(body)?;
+                encoder.finish()
+            },
+            HttpCompression::Zstd => zstd::stream::encode_all(body, 0)
+        }
+    }
+}
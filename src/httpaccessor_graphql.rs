@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use crate::httpaccessor::{HttpAccessor, HttpAccessorRequest, HttpAccessorResponseError};
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum GraphQLPathSegment {
+    Key(String),
+    Index(u64)
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct GraphQLLocation {
+    pub line: u32,
+    pub column: u32
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GraphQLError {
+    pub message: String,
+    #[serde(default)]
+    pub path: Vec<GraphQLPathSegment>,
+    #[serde(default)]
+    pub locations: Vec<GraphQLLocation>,
+    #[serde(default)]
+    pub extensions: Option<serde_json::Value>
+}
+
+#[derive(Debug)]
+pub struct GraphQLAccessorError {
+    pub err_type: GraphQLAccessorErrorType
+}
+
+#[derive(Debug)]
+pub enum GraphQLAccessorErrorType {
+    RequestError(HttpAccessorResponseError),
+    HttpStatusError(u16, String),
+    SerializeError(serde_json::Error),
+    DecodeError(serde_json::Error),
+    GraphQLErrors(Vec<GraphQLError>, Option<serde_json::Value>),
+    MissingData
+}
+
+#[derive(Serialize)]
+struct GraphQLRequestBody<'a, V: Serialize> {
+    query: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    variables: Option<&'a V>,
+    #[serde(rename = "operationName", skip_serializing_if = "Option::is_none")]
+    operation_name: Option<&'a str>
+}
+
+#[derive(Deserialize)]
+struct GraphQLResponseBody {
+    #[serde(default)]
+    data: Option<serde_json::Value>,
+    #[serde(default)]
+    errors: Option<Vec<GraphQLError>>
+}
+
+#[derive(Clone)]
+pub struct GraphQLAccessor {
+    http_accessor: HttpAccessor,
+    endpoint: String,
+    headers: Vec<(String, String)>
+}
+
+impl GraphQLAccessor {
+    pub fn new(http_accessor: HttpAccessor, endpoint: &str) -> Self {
+        Self {
+            http_accessor,
+            endpoint: endpoint.to_string(),
+            headers: Vec::new()
+        }
+    }
+
+    pub fn header(mut self, key: &str, val: &str) -> Self {
+        self.headers.push((key.to_string(), val.to_string()));
+        self
+    }
+
+    pub async fn query<T>(&self, query: &str) -> Result<T, GraphQLAccessorError>
+        where T: DeserializeOwned {
+        self.execute::<T, serde_json::Value>(query, None, None).await
+    }
+
+    pub async fn execute<T, V>(&self, query: &str, variables: Option<&V>, operation_name: Option<&str>) -> Result<T, GraphQLAccessorError>
+        where T: DeserializeOwned, V: Serialize {
+        let body = serde_json::to_vec(&GraphQLRequestBody {
+            query,
+            variables,
+            operation_name
+        }).map_err(|e| GraphQLAccessorError { err_type: GraphQLAccessorErrorType::SerializeError(e) })?;
+        let mut request = HttpAccessorRequest::new(reqwest::Method::POST, self.endpoint.as_str())
+            .header("Content-Type", "application/json")
+            .header("Accept", "application/json")
+            .body(body);
+        for (key, val) in self.headers.iter() {
+            request = request.header(key.as_str(), val.as_str());
+        }
+        let resp = self.http_accessor.execute(request).await
+            .map_err(|e| GraphQLAccessorError { err_type: GraphQLAccessorErrorType::RequestError(e) })?;
+        let resp_body: GraphQLResponseBody = match serde_json::from_str(resp.content()) {
+            Ok(resp_body) => resp_body,
+            Err(e) => {
+                return Err(GraphQLAccessorError {
+                    err_type: match resp.status_code() {
+                        200..=299 => GraphQLAccessorErrorType::DecodeError(e),
+                        status_code => GraphQLAccessorErrorType::HttpStatusError(status_code, resp.content().to_string())
+                    }
+                });
+            }
+        };
+        if let Some(errors) = resp_body.errors.filter(|errors| !errors.is_empty()) {
+            return Err(GraphQLAccessorError {
+                err_type: GraphQLAccessorErrorType::GraphQLErrors(errors, resp_body.data)
+            });
+        }
+        match resp_body.data {
+            Some(data) if !data.is_null() => serde_json::from_value(data)
+                .map_err(|e| GraphQLAccessorError { err_type: GraphQLAccessorErrorType::DecodeError(e) }),
+            _ => Err(GraphQLAccessorError {
+                err_type: match resp.status_code() {
+                    200..=299 => GraphQLAccessorErrorType::MissingData,
+                    status_code => GraphQLAccessorErrorType::HttpStatusError(status_code, resp.content().to_string())
+                }
+            })
+        }
+    }
+}
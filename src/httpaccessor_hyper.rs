@@ -0,0 +1,133 @@
+use tokio::time::Duration;
+use futures::future::BoxFuture;
+use hyper::client::HttpConnector;
+use hyper_rustls::HttpsConnector;
+use crate::httpaccessor::{HttpAccessorError, HttpAccessorRequest, HttpAccessorResponse, HttpAccessorResponseError};
+use crate::httpaccessor_transport::{HttpTransport, HttpTransportErrorKind};
+use crate::httpaccessor_resolver::HttpResolverService;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HttpProtocol {
+    Http1Only,
+    Http2Alpn,
+    Http2PriorKnowledge
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct HttpProtocolOptions {
+    pub(crate) protocol: HttpProtocol,
+    pub(crate) http2_keep_alive_interval: Option<u32>,
+    pub(crate) http2_keep_alive_timeout: u32,
+    pub(crate) http2_keep_alive_while_idle: bool
+}
+
+fn native_root_store() -> rustls::RootCertStore {
+    match rustls_native_certs::load_native_certs() {
+        Ok(root_store) => root_store,
+        Err((root_store, e)) => {
+            log::warn!("load native root certificates fail: {}", e);
+            root_store.unwrap_or_else(rustls::RootCertStore::empty)
+        }
+    }
+}
+
+pub(crate) fn build_hyper_request(request: &HttpAccessorRequest, uri: &str, host: Option<&str>) -> Result<hyper::Request<hyper::Body>, String> {
+    let mut request_builder = hyper::Request::builder()
+        .method(request.method().clone())
+        .uri(uri);
+    if let Some(host) = host.filter(|_| request.header_value("host").is_none()) {
+        request_builder = request_builder.header("Host", host);
+    }
+    for (key, val) in request.headers() {
+        request_builder = request_builder.header(key.as_str(), val.as_str());
+    }
+    request_builder
+        .body(hyper::Body::from(request.body_bytes().map(|body| body.to_vec()).unwrap_or_default()))
+        .map_err(|e| format!("build request fail: {}", e))
+}
+
+pub(crate) async fn handle_hyper_resp(url: &str, resp: hyper::Response<hyper::Body>) -> Result<HttpAccessorResponse, HttpAccessorResponseError> {
+    let status_code = resp.status().as_u16();
+    let version = resp.version();
+    let headers: Vec<(String, String)> = resp.headers().iter()
+        .map(|(k, v)| (k.as_str().to_string(), String::from_utf8_lossy(v.as_bytes()).to_string()))
+        .collect();
+    let body = hyper::body::to_bytes(resp.into_body()).await
+        .map_err(|e| HttpAccessorResponseError {
+            url: url.to_string(),
+            status_code: Some(status_code),
+            err: Some(HttpAccessorError::transport(HttpTransportErrorKind::Other, format!("read body fail: {}", e).as_str()))
+        })?;
+    let mut accessor_resp = HttpAccessorResponse::new(url, status_code, String::from_utf8_lossy(&body).as_ref());
+    accessor_resp.headers = headers;
+    accessor_resp.version = version;
+    Ok(accessor_resp)
+}
+
+pub struct HyperTransport {
+    client: hyper::Client<HttpsConnector<HttpConnector<HttpResolverService>>, hyper::Body>
+}
+
+impl HyperTransport {
+    pub(crate) fn new(options: &HttpProtocolOptions, resolver: HttpResolverService) -> Self {
+        let mut http_connector = HttpConnector::new_with_resolver(resolver);
+        http_connector.enforce_http(false);
+        let mut tls_config = rustls::ClientConfig::new();
+        tls_config.root_store = native_root_store();
+        tls_config.alpn_protocols = match options.protocol {
+            HttpProtocol::Http1Only => vec![b"http/1.1".to_vec()],
+            HttpProtocol::Http2Alpn => vec![b"h2".to_vec(), b"http/1.1".to_vec()],
+            HttpProtocol::Http2PriorKnowledge => vec![b"h2".to_vec()]
+        };
+        let https_connector: HttpsConnector<HttpConnector<HttpResolverService>> = (http_connector, tls_config).into();
+        let mut client_builder = hyper::Client::builder();
+        client_builder.http2_only(options.protocol == HttpProtocol::Http2PriorKnowledge);
+        if let Some(interval) = options.http2_keep_alive_interval {
+            client_builder
+                .http2_keep_alive_interval(Duration::from_secs(interval as u64))
+                .http2_keep_alive_timeout(Duration::from_secs(options.http2_keep_alive_timeout as u64))
+                .http2_keep_alive_while_idle(options.http2_keep_alive_while_idle);
+        }
+        Self {
+            client: client_builder.build(https_connector)
+        }
+    }
+
+    async fn send_request(&self, request: HttpAccessorRequest) -> Result<HttpAccessorResponse, HttpAccessorResponseError> {
+        let url = request.url().to_string();
+        let hyper_request = build_hyper_request(&request, url.as_str(), None).map_err(|msg| HttpAccessorResponseError {
+            url: url.clone(),
+            status_code: None,
+            err: Some(HttpAccessorError::transport(HttpTransportErrorKind::Other, msg.as_str()))
+        })?;
+        let resp = self.client.request(hyper_request).await.map_err(|e| HttpAccessorResponseError {
+            url: url.clone(),
+            status_code: None,
+            err: Some(HttpAccessorError::transport(
+                match e.is_connect() {
+                    true => HttpTransportErrorKind::Connect,
+                    false => HttpTransportErrorKind::Other
+                },
+                format!("send request fail: {}", e).as_str()
+            ))
+        })?;
+        handle_hyper_resp(url.as_str(), resp).await
+    }
+}
+
+impl HttpTransport for HyperTransport {
+    fn send(&self, request: HttpAccessorRequest) -> BoxFuture<'_, Result<HttpAccessorResponse, HttpAccessorResponseError>> {
+        Box::pin(async move {
+            let url = request.url().to_string();
+            match request.timeout_secs() {
+                Some(timeout) => tokio::time::timeout(Duration::from_secs(timeout as u64), self.send_request(request)).await
+                    .unwrap_or_else(|_| Err(HttpAccessorResponseError {
+                        url,
+                        status_code: None,
+                        err: Some(HttpAccessorError::transport(HttpTransportErrorKind::Timeout, "request timeout"))
+                    })),
+                None => self.send_request(request).await
+            }
+        })
+    }
+}
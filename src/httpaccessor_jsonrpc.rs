@@ -0,0 +1,266 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use crate::httpaccessor::{HttpAccessor, HttpAccessorRequest, HttpAccessorResponse, HttpAccessorResponseError};
+
+const JSONRPC_VERSION: &str = "2.0";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JsonRpcErrorKind {
+    ParseError,
+    InvalidRequest,
+    MethodNotFound,
+    InvalidParams,
+    InternalError,
+    ServerError,
+    ApplicationError
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct JsonRpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(default)]
+    pub data: Option<serde_json::Value>
+}
+
+impl JsonRpcError {
+    pub fn kind(&self) -> JsonRpcErrorKind {
+        match self.code {
+            -32700 => JsonRpcErrorKind::ParseError,
+            -32600 => JsonRpcErrorKind::InvalidRequest,
+            -32601 => JsonRpcErrorKind::MethodNotFound,
+            -32602 => JsonRpcErrorKind::InvalidParams,
+            -32603 => JsonRpcErrorKind::InternalError,
+            -32099..=-32000 => JsonRpcErrorKind::ServerError,
+            _ => JsonRpcErrorKind::ApplicationError
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct JsonRpcAccessorError {
+    pub err_type: JsonRpcAccessorErrorType
+}
+
+#[derive(Debug)]
+pub enum JsonRpcAccessorErrorType {
+    RequestError(HttpAccessorResponseError),
+    HttpStatusError(u16, String),
+    SerializeError(serde_json::Error),
+    DecodeError(serde_json::Error),
+    RpcError(JsonRpcError),
+    MissingResponse(u64)
+}
+
+#[derive(Serialize)]
+struct JsonRpcRequestBody {
+    jsonrpc: &'static str,
+    method: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    params: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<u64>
+}
+
+#[derive(Deserialize)]
+struct JsonRpcResponseBody {
+    #[serde(default)]
+    id: Option<u64>,
+    #[serde(default)]
+    result: Option<serde_json::Value>,
+    #[serde(default)]
+    error: Option<JsonRpcError>
+}
+
+pub struct JsonRpcBatch {
+    calls: Vec<(String, Option<serde_json::Value>, bool)>
+}
+
+impl Default for JsonRpcBatch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JsonRpcBatch {
+    pub fn new() -> Self {
+        Self {
+            calls: Vec::new()
+        }
+    }
+
+    pub fn call(mut self, method: &str, params: serde_json::Value) -> Self {
+        self.calls.push((method.to_string(), Some(params), false));
+        self
+    }
+
+    pub fn notify(mut self, method: &str, params: serde_json::Value) -> Self {
+        self.calls.push((method.to_string(), Some(params), true));
+        self
+    }
+
+    /// Calls and notifications together; `batch_call` returns one result per call, see `call_count`.
+    pub fn len(&self) -> usize {
+        self.calls.len()
+    }
+
+    pub fn call_count(&self) -> usize {
+        self.calls.iter().filter(|(_, _, is_notification)| !is_notification).count()
+    }
+
+    pub fn notification_count(&self) -> usize {
+        self.calls.iter().filter(|(_, _, is_notification)| *is_notification).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.calls.is_empty()
+    }
+}
+
+#[derive(Clone)]
+pub struct JsonRpcAccessor {
+    http_accessor: HttpAccessor,
+    endpoint: String,
+    headers: Vec<(String, String)>,
+    next_id: Arc<AtomicU64>,
+    retry_transient: bool
+}
+
+impl JsonRpcAccessor {
+    pub fn new(http_accessor: HttpAccessor, endpoint: &str) -> Self {
+        Self {
+            http_accessor,
+            endpoint: endpoint.to_string(),
+            headers: Vec::new(),
+            next_id: Arc::new(AtomicU64::new(1)),
+            retry_transient: false
+        }
+    }
+
+    pub fn header(mut self, key: &str, val: &str) -> Self {
+        self.headers.push((key.to_string(), val.to_string()));
+        self
+    }
+
+    pub fn retry_transient(mut self, retry_transient: bool) -> Self {
+        self.retry_transient = retry_transient;
+        self
+    }
+
+    async fn post_json<B: Serialize>(&self, body: &B) -> Result<HttpAccessorResponse, JsonRpcAccessorError> {
+        let body = serde_json::to_vec(body)
+            .map_err(|e| JsonRpcAccessorError { err_type: JsonRpcAccessorErrorType::SerializeError(e) })?;
+        let mut request = HttpAccessorRequest::new(reqwest::Method::POST, self.endpoint.as_str())
+            .header("Content-Type", "application/json")
+            .body(body)
+            .retryable(self.retry_transient);
+        for (key, val) in self.headers.iter() {
+            request = request.header(key.as_str(), val.as_str());
+        }
+        self.http_accessor.execute(request).await
+            .map_err(|e| JsonRpcAccessorError { err_type: JsonRpcAccessorErrorType::RequestError(e) })
+    }
+
+    fn decode_body<T: DeserializeOwned>(resp: &HttpAccessorResponse) -> Result<T, JsonRpcAccessorError> {
+        serde_json::from_str(resp.content()).map_err(|e| JsonRpcAccessorError {
+            err_type: match resp.status_code() {
+                200..=299 => JsonRpcAccessorErrorType::DecodeError(e),
+                status_code => JsonRpcAccessorErrorType::HttpStatusError(status_code, resp.content().to_string())
+            }
+        })
+    }
+
+    pub async fn call<T, P>(&self, method: &str, params: &P) -> Result<T, JsonRpcAccessorError>
+        where T: DeserializeOwned, P: Serialize {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let params = serde_json::to_value(params)
+            .map_err(|e| JsonRpcAccessorError { err_type: JsonRpcAccessorErrorType::SerializeError(e) })?;
+        let resp = self.post_json(&JsonRpcRequestBody {
+            jsonrpc: JSONRPC_VERSION,
+            method: method.to_string(),
+            params: Some(params),
+            id: Some(id)
+        }).await?;
+        let resp_body: JsonRpcResponseBody = Self::decode_body(&resp)?;
+        if let Some(err) = resp_body.error {
+            return Err(JsonRpcAccessorError { err_type: JsonRpcAccessorErrorType::RpcError(err) });
+        }
+        serde_json::from_value(resp_body.result.unwrap_or(serde_json::Value::Null))
+            .map_err(|e| JsonRpcAccessorError { err_type: JsonRpcAccessorErrorType::DecodeError(e) })
+    }
+
+    pub async fn notify<P>(&self, method: &str, params: &P) -> Result<(), JsonRpcAccessorError>
+        where P: Serialize {
+        let params = serde_json::to_value(params)
+            .map_err(|e| JsonRpcAccessorError { err_type: JsonRpcAccessorErrorType::SerializeError(e) })?;
+        let resp = self.post_json(&JsonRpcRequestBody {
+            jsonrpc: JSONRPC_VERSION,
+            method: method.to_string(),
+            params: Some(params),
+            id: None
+        }).await?;
+        match resp.status_code() {
+            200..=299 => Ok(()),
+            status_code => Err(JsonRpcAccessorError {
+                err_type: JsonRpcAccessorErrorType::HttpStatusError(status_code, resp.content().to_string())
+            })
+        }
+    }
+
+    /// Returns one result per `JsonRpcBatch::call`, in call order; notifications get no entry.
+    pub async fn batch_call(&self, batch: JsonRpcBatch) -> Result<Vec<Result<serde_json::Value, JsonRpcError>>, JsonRpcAccessorError> {
+        if batch.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut ids = Vec::new();
+        let body: Vec<JsonRpcRequestBody> = batch.calls.into_iter().map(|(method, params, is_notification)| {
+            let id = match is_notification {
+                true => None,
+                false => {
+                    let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+                    ids.push(id);
+                    Some(id)
+                }
+            };
+            JsonRpcRequestBody {
+                jsonrpc: JSONRPC_VERSION,
+                method,
+                params,
+                id
+            }
+        }).collect();
+        let resp = self.post_json(&body).await?;
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let resp_value: serde_json::Value = Self::decode_body(&resp)?;
+        if !resp_value.is_array() {
+            let resp_body: JsonRpcResponseBody = serde_json::from_value(resp_value)
+                .map_err(|e| JsonRpcAccessorError { err_type: JsonRpcAccessorErrorType::DecodeError(e) })?;
+            return Err(JsonRpcAccessorError {
+                err_type: match resp_body.error {
+                    Some(err) => JsonRpcAccessorErrorType::RpcError(err),
+                    None => JsonRpcAccessorErrorType::MissingResponse(ids[0])
+                }
+            });
+        }
+        let resp_body_list: Vec<JsonRpcResponseBody> = serde_json::from_value(resp_value)
+            .map_err(|e| JsonRpcAccessorError { err_type: JsonRpcAccessorErrorType::DecodeError(e) })?;
+        let mut resp_map: HashMap<u64, JsonRpcResponseBody> = resp_body_list.into_iter()
+            .filter_map(|resp_body| resp_body.id.map(|id| (id, resp_body)))
+            .collect();
+        let mut rst = Vec::with_capacity(ids.len());
+        for id in ids {
+            let resp_body = resp_map.remove(&id)
+                .ok_or(JsonRpcAccessorError { err_type: JsonRpcAccessorErrorType::MissingResponse(id) })?;
+            rst.push(match resp_body.error {
+                Some(err) => Err(err),
+                None => Ok(resp_body.result.unwrap_or(serde_json::Value::Null))
+            });
+        }
+        Ok(rst)
+    }
+}
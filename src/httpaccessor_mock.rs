@@ -0,0 +1,211 @@
+use std::sync::{Arc, Mutex};
+use tokio::time::Duration;
+use futures::future::BoxFuture;
+use crate::httpaccessor::{HttpAccessorError, HttpAccessorRequest, HttpAccessorResponse, HttpAccessorResponseError};
+use crate::httpaccessor_transport::{HttpTransport, HttpTransportErrorKind};
+
+#[derive(Debug, Clone)]
+pub enum HttpMockReply {
+    Response(HttpAccessorResponse),
+    Error(HttpTransportErrorKind)
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpMockRoute {
+    pub(crate) method: Option<reqwest::Method>,
+    pub(crate) url: String,
+    pub(crate) body_contains: Option<String>,
+    pub(crate) reply: HttpMockReply,
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) delay: Option<u64>,
+    pub(crate) times: Option<usize>
+}
+
+fn url_path(url: &str) -> &str {
+    let without_scheme = match url.find("://") {
+        Some(pos) => &url[pos + 3..],
+        None => url
+    };
+    match without_scheme.find('/') {
+        Some(pos) => &without_scheme[pos..],
+        None => "/"
+    }
+}
+
+impl HttpMockRoute {
+    pub fn new(method: Option<reqwest::Method>, url: &str) -> Self {
+        Self {
+            method,
+            url: url.to_string(),
+            body_contains: None,
+            reply: HttpMockReply::Response(HttpAccessorResponse::new(url, 200, "")),
+            headers: Vec::new(),
+            delay: None,
+            times: None
+        }
+    }
+
+    pub fn any(url: &str) -> Self {
+        Self::new(None, url)
+    }
+
+    pub fn get(url: &str) -> Self {
+        Self::new(Some(reqwest::Method::GET), url)
+    }
+
+    pub fn post(url: &str) -> Self {
+        Self::new(Some(reqwest::Method::POST), url)
+    }
+
+    pub fn body_contains(mut self, body_contains: &str) -> Self {
+        self.body_contains = Some(body_contains.to_string());
+        self
+    }
+
+    pub fn respond(mut self, status_code: u16, content: &str) -> Self {
+        self.reply = HttpMockReply::Response(HttpAccessorResponse::new(self.url.as_str(), status_code, content));
+        self
+    }
+
+    pub fn header(mut self, key: &str, val: &str) -> Self {
+        self.headers.push((key.to_string(), val.to_string()));
+        self
+    }
+
+    pub fn error(mut self, kind: HttpTransportErrorKind) -> Self {
+        self.reply = HttpMockReply::Error(kind);
+        self
+    }
+
+    pub fn delay(mut self, millis: u64) -> Self {
+        self.delay = Some(millis);
+        self
+    }
+
+    pub fn times(mut self, times: usize) -> Self {
+        self.times = Some(times);
+        self
+    }
+
+    fn matches(&self, request: &HttpAccessorRequest) -> bool {
+        if let Some(method) = self.method.as_ref() {
+            if method != request.method() {
+                return false;
+            }
+        }
+        let url_matched = if self.url.ends_with('*') {
+            let prefix = self.url.trim_end_matches('*');
+            match prefix.starts_with('/') {
+                true => url_path(request.url()).starts_with(prefix),
+                false => request.url().starts_with(prefix)
+            }
+        } else if self.url.starts_with('/') {
+            url_path(request.url()) == self.url
+        } else {
+            request.url() == self.url
+        };
+        if !url_matched {
+            return false;
+        }
+        match self.body_contains.as_ref() {
+            Some(needle) => request.body_bytes()
+                .is_some_and(|body| String::from_utf8_lossy(body).contains(needle.as_str())),
+            None => true
+        }
+    }
+}
+
+struct HttpMockState {
+    routes: Vec<(HttpMockRoute, usize)>,
+    requests: Vec<HttpAccessorRequest>
+}
+
+#[derive(Clone)]
+pub struct HttpMockTransport {
+    state: Arc<Mutex<HttpMockState>>
+}
+
+impl Default for HttpMockTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpMockTransport {
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(HttpMockState {
+                routes: Vec::new(),
+                requests: Vec::new()
+            }))
+        }
+    }
+
+    pub fn route(self, route: HttpMockRoute) -> Self {
+        self.add_route(route);
+        self
+    }
+
+    pub fn add_route(&self, route: HttpMockRoute) {
+        self.state.lock().unwrap().routes.push((route, 0));
+    }
+
+    pub fn requests(&self) -> Vec<HttpAccessorRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    pub fn request_count(&self) -> usize {
+        self.state.lock().unwrap().requests.len()
+    }
+
+    pub fn clear_requests(&self) {
+        self.state.lock().unwrap().requests.clear();
+    }
+
+    fn match_route(&self, request: &HttpAccessorRequest) -> Option<HttpMockRoute> {
+        let mut state = self.state.lock().unwrap();
+        state.requests.push(request.clone());
+        for (route, hits) in state.routes.iter_mut() {
+            if route.times.is_some_and(|times| *hits >= times) || !route.matches(request) {
+                continue;
+            }
+            *hits += 1;
+            return Some(route.clone());
+        }
+        None
+    }
+}
+
+impl HttpTransport for HttpMockTransport {
+    fn send(&self, request: HttpAccessorRequest) -> BoxFuture<'_, Result<HttpAccessorResponse, HttpAccessorResponseError>> {
+        let route = self.match_route(&request);
+        Box::pin(async move {
+            let url = request.url().to_string();
+            let gen_err = |kind: HttpTransportErrorKind, msg: &str| HttpAccessorResponseError {
+                url: url.clone(),
+                status_code: None,
+                err: Some(HttpAccessorError::transport(kind, msg))
+            };
+            let route = match route {
+                Some(route) => route,
+                None => return Err(gen_err(HttpTransportErrorKind::Other, "no mock route matched"))
+            };
+            if let Some(delay) = route.delay {
+                let timeout_millis = request.timeout_secs().map(|timeout| timeout as u64 * 1000);
+                if let Some(timeout_millis) = timeout_millis.filter(|timeout_millis| *timeout_millis < delay) {
+                    tokio::time::delay_for(Duration::from_millis(timeout_millis)).await;
+                    return Err(gen_err(HttpTransportErrorKind::Timeout, "mock request timeout"));
+                }
+                tokio::time::delay_for(Duration::from_millis(delay)).await;
+            }
+            match route.reply {
+                HttpMockReply::Response(mut resp) => {
+                    resp.url = url.clone();
+                    resp.headers.extend(route.headers);
+                    Ok(resp)
+                },
+                HttpMockReply::Error(kind) => Err(gen_err(kind, "mock transport error"))
+            }
+        })
+    }
+}
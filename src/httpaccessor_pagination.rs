@@ -0,0 +1,236 @@
+use tokio::time::Duration;
+use futures::StreamExt;
+use futures::stream::BoxStream;
+use serde::de::DeserializeOwned;
+use crate::httpaccessor::{HttpAccessor, HttpAccessorRequest, HttpAccessorResponse, HttpAccessorResponseError};
+
+const RELATIVE_URL_BASE: &str = "http://relative.local";
+
+#[derive(Debug, Clone)]
+pub enum HttpPaginationStrategy {
+    LinkHeader,
+    Cursor { cursor_param: String, cursor_pointer: String },
+    PageNumber { page_param: String, start_page: u64 },
+    Offset { offset_param: String, limit_param: String, limit: u64 }
+}
+
+#[derive(Debug)]
+pub struct HttpPaginationError {
+    pub err_type: HttpPaginationErrorType
+}
+
+#[derive(Debug)]
+pub enum HttpPaginationErrorType {
+    RequestError(HttpAccessorResponseError),
+    HttpStatusError(u16, String),
+    DecodeError(serde_json::Error),
+    MissingItems(String),
+    InvalidUrl(String),
+    MaxPagesExceeded(u32)
+}
+
+#[derive(Clone)]
+pub struct HttpPaginator {
+    http_accessor: HttpAccessor,
+    url: String,
+    strategy: HttpPaginationStrategy,
+    items_pointer: String,
+    max_pages: u32,
+    page_retries: u32
+}
+
+struct HttpPaginationState {
+    next_url: Option<String>,
+    page: u32,
+    page_value: u64,
+    done: bool
+}
+
+fn parse_url(url: &str) -> Result<(reqwest::Url, bool), HttpPaginationError> {
+    let is_relative = url.starts_with('/');
+    let parsed = match is_relative {
+        true => reqwest::Url::parse(RELATIVE_URL_BASE).and_then(|base| base.join(url)),
+        false => reqwest::Url::parse(url)
+    };
+    parsed.map(|parsed| (parsed, is_relative))
+        .map_err(|_| HttpPaginationError { err_type: HttpPaginationErrorType::InvalidUrl(url.to_string()) })
+}
+
+fn format_url(url: &reqwest::Url, is_relative: bool) -> String {
+    match is_relative {
+        true => url.as_str().trim_start_matches(RELATIVE_URL_BASE).to_string(),
+        false => url.to_string()
+    }
+}
+
+fn set_query_param(url: &str, key: &str, val: &str) -> Result<String, HttpPaginationError> {
+    let (mut parsed, is_relative) = parse_url(url)?;
+    let pairs: Vec<(String, String)> = parsed.query_pairs()
+        .filter(|(k, _)| k != key)
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    parsed.query_pairs_mut().clear().extend_pairs(pairs).append_pair(key, val);
+    Ok(format_url(&parsed, is_relative))
+}
+
+fn parse_next_link(current_url: &str, link_header: &str) -> Result<Option<String>, HttpPaginationError> {
+    for link in link_header.split(',') {
+        let mut parts = link.split(';');
+        let target = parts.next().unwrap_or("").trim();
+        let is_next = parts.any(|param| {
+            let param = param.trim().replace(' ', "");
+            param == "rel=\"next\"" || param == "rel=next"
+        });
+        if is_next && target.starts_with('<') && target.ends_with('>') {
+            let (current, is_relative) = parse_url(current_url)?;
+            let next = current.join(&target[1..target.len() - 1])
+                .map_err(|_| HttpPaginationError { err_type: HttpPaginationErrorType::InvalidUrl(target.to_string()) })?;
+            return Ok(Some(format_url(&next, is_relative)));
+        }
+    }
+    Ok(None)
+}
+
+impl HttpPaginator {
+    pub fn new(http_accessor: HttpAccessor, url: &str, strategy: HttpPaginationStrategy) -> Self {
+        Self {
+            http_accessor,
+            url: url.to_string(),
+            strategy,
+            items_pointer: String::new(),
+            max_pages: 100,
+            page_retries: 0
+        }
+    }
+
+    pub fn items_pointer(mut self, items_pointer: &str) -> Self {
+        self.items_pointer = items_pointer.to_string();
+        self
+    }
+
+    pub fn max_pages(mut self, max_pages: u32) -> Self {
+        self.max_pages = max_pages;
+        self
+    }
+
+    pub fn page_retries(mut self, page_retries: u32) -> Self {
+        self.page_retries = page_retries;
+        self
+    }
+
+    async fn fetch_page(&self, url: &str) -> Result<HttpAccessorResponse, HttpPaginationError> {
+        let mut attempt = 0;
+        loop {
+            let rst = match self.http_accessor.execute(HttpAccessorRequest::get(url)).await {
+                Ok(resp) if resp.status_code() >= 200 && resp.status_code() < 300 => Ok(resp),
+                Ok(resp) => Err(HttpPaginationError {
+                    err_type: HttpPaginationErrorType::HttpStatusError(resp.status_code(), resp.content().to_string())
+                }),
+                Err(e) => Err(HttpPaginationError { err_type: HttpPaginationErrorType::RequestError(e) })
+            };
+            if rst.is_ok() || attempt >= self.page_retries {
+                return rst;
+            }
+            attempt += 1;
+            tokio::time::delay_for(Duration::from_millis(self.http_accessor.retry_interval * attempt as u64)).await;
+        }
+    }
+
+    fn first_url(&self) -> Result<(String, u64), HttpPaginationError> {
+        match &self.strategy {
+            HttpPaginationStrategy::LinkHeader | HttpPaginationStrategy::Cursor { .. } => Ok((self.url.clone(), 0)),
+            HttpPaginationStrategy::PageNumber { page_param, start_page } => {
+                Ok((set_query_param(self.url.as_str(), page_param, start_page.to_string().as_str())?, *start_page))
+            },
+            HttpPaginationStrategy::Offset { offset_param, limit_param, limit } => {
+                let url = set_query_param(self.url.as_str(), offset_param, "0")?;
+                Ok((set_query_param(url.as_str(), limit_param, limit.to_string().as_str())?, 0))
+            }
+        }
+    }
+
+    fn next_url(&self, url: &str, page_value: u64, resp: &HttpAccessorResponse, body: &serde_json::Value, item_count: usize) -> Result<Option<(String, u64)>, HttpPaginationError> {
+        match &self.strategy {
+            HttpPaginationStrategy::LinkHeader => match resp.header_value("link") {
+                Some(link_header) => Ok(parse_next_link(url, link_header)?.map(|next_url| (next_url, 0))),
+                None => Ok(None)
+            },
+            HttpPaginationStrategy::Cursor { cursor_param, cursor_pointer } => {
+                let cursor = match body.pointer(cursor_pointer.as_str()) {
+                    Some(serde_json::Value::String(cursor)) if !cursor.is_empty() => cursor.clone(),
+                    Some(serde_json::Value::Number(cursor)) => cursor.to_string(),
+                    _ => return Ok(None)
+                };
+                Ok(Some((set_query_param(self.url.as_str(), cursor_param, cursor.as_str())?, 0)))
+            },
+            HttpPaginationStrategy::PageNumber { page_param, .. } => {
+                if item_count == 0 {
+                    return Ok(None);
+                }
+                let next_page = page_value + 1;
+                Ok(Some((set_query_param(self.url.as_str(), page_param, next_page.to_string().as_str())?, next_page)))
+            },
+            HttpPaginationStrategy::Offset { offset_param, limit_param, limit } => {
+                if (item_count as u64) < *limit {
+                    return Ok(None);
+                }
+                let next_offset = page_value + limit;
+                let next_url = set_query_param(self.url.as_str(), offset_param, next_offset.to_string().as_str())?;
+                Ok(Some((set_query_param(next_url.as_str(), limit_param, limit.to_string().as_str())?, next_offset)))
+            }
+        }
+    }
+
+    async fn fetch_items<T: DeserializeOwned>(&self, url: &str, page_value: u64) -> Result<(Vec<T>, Option<(String, u64)>), HttpPaginationError> {
+        let resp = self.fetch_page(url).await?;
+        let body: serde_json::Value = serde_json::from_str(resp.content())
+            .map_err(|e| HttpPaginationError { err_type: HttpPaginationErrorType::DecodeError(e) })?;
+        let items_value = body.pointer(self.items_pointer.as_str())
+            .cloned()
+            .ok_or(HttpPaginationError { err_type: HttpPaginationErrorType::MissingItems(self.items_pointer.clone()) })?;
+        let items: Vec<T> = serde_json::from_value(items_value)
+            .map_err(|e| HttpPaginationError { err_type: HttpPaginationErrorType::DecodeError(e) })?;
+        let next = self.next_url(url, page_value, &resp, &body, items.len())?;
+        Ok((items, next))
+    }
+
+    pub fn items<T>(self) -> BoxStream<'static, Result<T, HttpPaginationError>>
+        where T: DeserializeOwned + Send + 'static {
+        let (first_url, first_page_value) = match self.first_url() {
+            Ok(first) => first,
+            Err(e) => return futures::stream::iter(vec![Err(e)]).boxed()
+        };
+        let state = HttpPaginationState {
+            next_url: Some(first_url),
+            page: 0,
+            page_value: first_page_value,
+            done: false
+        };
+        futures::stream::unfold((self, state), |(paginator, mut state)| async move {
+            if state.done {
+                return None;
+            }
+            let url = state.next_url.take()?;
+            if state.page >= paginator.max_pages {
+                state.done = true;
+                let max_pages = paginator.max_pages;
+                return Some((vec![Err(HttpPaginationError { err_type: HttpPaginationErrorType::MaxPagesExceeded(max_pages) })], (paginator, state)));
+            }
+            state.page += 1;
+            let page_items = match paginator.fetch_items::<T>(url.as_str(), state.page_value).await {
+                Ok((items, next)) => {
+                    if let Some((next_url, next_page_value)) = next {
+                        state.next_url = Some(next_url);
+                        state.page_value = next_page_value;
+                    }
+                    items.into_iter().map(Ok).collect()
+                },
+                Err(e) => {
+                    state.done = true;
+                    vec![Err(e)]
+                }
+            };
+            Some((page_items, (paginator, state)))
+        }).flat_map(futures::stream::iter).boxed()
+    }
+}
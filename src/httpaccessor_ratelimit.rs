@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use crate::httpaccessor::HttpAccessorResponse;
+
+struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    tokens: f64,
+    last_refill: Instant,
+    blocked_until: Option<Instant>
+}
+
+impl TokenBucket {
+    fn new(rate: f64, burst: u32) -> Self {
+        let capacity = std::cmp::max(burst, 1) as f64;
+        Self {
+            rate,
+            capacity,
+            tokens: capacity,
+            last_refill: Instant::now(),
+            blocked_until: None
+        }
+    }
+
+    fn reserve(&mut self) -> Duration {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.last_refill = now;
+        self.tokens -= 1.0;
+        let mut wait = match self.tokens >= 0.0 || self.rate <= 0.0 {
+            true => Duration::from_secs(0),
+            false => Duration::from_secs_f64(-self.tokens / self.rate)
+        };
+        if let Some(blocked_until) = self.blocked_until {
+            match blocked_until > now {
+                true => wait = std::cmp::max(wait, blocked_until - now),
+                false => self.blocked_until = None
+            }
+        }
+        wait
+    }
+
+    fn block_for(&mut self, duration: Duration) {
+        let until = Instant::now() + duration;
+        if self.blocked_until.is_none_or(|blocked_until| blocked_until < until) {
+            self.blocked_until = Some(until);
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct HttpRateLimiter {
+    global: Option<Arc<Mutex<TokenBucket>>>,
+    per_host: Option<(f64, u32)>,
+    hosts: Arc<Mutex<HashMap<String, Arc<Mutex<TokenBucket>>>>>,
+    pub(crate) adaptive: bool
+}
+
+fn parse_reset(val: &str) -> Option<Duration> {
+    let secs = val.trim().parse::<f64>().ok().filter(|secs| *secs >= 0.0)?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs_f64();
+    match secs > 1_000_000_000.0 {
+        true => Some(Duration::from_secs_f64((secs - now).max(0.0))),
+        false => Some(Duration::from_secs_f64(secs))
+    }
+}
+
+impl Default for HttpRateLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpRateLimiter {
+    pub fn new() -> Self {
+        Self {
+            global: None,
+            per_host: None,
+            hosts: Arc::new(Mutex::new(HashMap::new())),
+            adaptive: false
+        }
+    }
+
+    pub fn global(mut self, rate: f64, burst: u32) -> Self {
+        self.global = Some(Arc::new(Mutex::new(TokenBucket::new(rate, burst))));
+        self
+    }
+
+    pub fn per_host(mut self, rate: f64, burst: u32) -> Self {
+        self.per_host = Some((rate, burst));
+        self
+    }
+
+    pub fn adaptive(mut self, adaptive: bool) -> Self {
+        self.adaptive = adaptive;
+        self
+    }
+
+    fn host_bucket(&self, host: &str) -> Arc<Mutex<TokenBucket>> {
+        let mut hosts = self.hosts.lock().unwrap();
+        hosts.entry(host.to_string()).or_insert_with(|| {
+            let (rate, burst) = self.per_host.unwrap_or((0.0, 1));
+            Arc::new(Mutex::new(TokenBucket::new(rate, burst)))
+        }).clone()
+    }
+
+    pub async fn acquire(&self, host: &str) {
+        let mut wait = match self.global.as_ref() {
+            Some(global) => global.lock().unwrap().reserve(),
+            None => Duration::from_secs(0)
+        };
+        if self.per_host.is_some() || self.adaptive {
+            wait = std::cmp::max(wait, self.host_bucket(host).lock().unwrap().reserve());
+        }
+        if wait > Duration::from_secs(0) {
+            tokio::time::delay_for(wait).await;
+        }
+    }
+
+    pub fn observe(&self, host: &str, resp: &HttpAccessorResponse) {
+        if !self.adaptive {
+            return;
+        }
+        let retry_after = match resp.status_code() {
+            429 | 503 => resp.header_value("retry-after").and_then(parse_reset),
+            _ => None
+        };
+        let remaining = resp.header_value("x-ratelimit-remaining")
+            .and_then(|remaining| remaining.trim().parse::<u64>().ok());
+        let reset = resp.header_value("x-ratelimit-reset").and_then(parse_reset);
+        let block = match (retry_after, remaining, reset) {
+            (Some(retry_after), _, _) => Some(retry_after),
+            (None, Some(0), Some(reset)) => Some(reset),
+            _ => None
+        };
+        if let Some(block) = block {
+            log::debug!("rate limit reached on {}, wait {:?}", host, block);
+            self.host_bucket(host).lock().unwrap().block_for(block);
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use std::io;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use futures::future::BoxFuture;
+use futures::task::{Context, Poll};
+use hyper::client::connect::dns::Name;
+
+pub trait HttpResolver: Send + Sync {
+    fn resolve(&self, host: &str) -> BoxFuture<'_, Result<Vec<IpAddr>, io::Error>>;
+}
+
+pub struct HttpSystemResolver;
+
+impl HttpResolver for HttpSystemResolver {
+    fn resolve(&self, host: &str) -> BoxFuture<'_, Result<Vec<IpAddr>, io::Error>> {
+        let host = host.to_string();
+        Box::pin(async move {
+            let addrs = tokio::net::lookup_host((host.as_str(), 0)).await?;
+            Ok(addrs.map(|addr| addr.ip()).collect())
+        })
+    }
+}
+
+pub struct HttpCachingResolver {
+    inner: Arc<dyn HttpResolver>,
+    ttl: Duration,
+    cache: Mutex<HashMap<String, (Instant, Vec<IpAddr>)>>
+}
+
+impl HttpCachingResolver {
+    pub fn new<R: HttpResolver + 'static>(inner: R, ttl_secs: u64) -> Self {
+        Self {
+            inner: Arc::new(inner),
+            ttl: Duration::from_secs(ttl_secs),
+            cache: Mutex::new(HashMap::new())
+        }
+    }
+
+    pub fn clear(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+}
+
+impl HttpResolver for HttpCachingResolver {
+    fn resolve(&self, host: &str) -> BoxFuture<'_, Result<Vec<IpAddr>, io::Error>> {
+        let host = host.to_lowercase();
+        Box::pin(async move {
+            if let Some((resolved_at, addrs)) = self.cache.lock().unwrap().get(host.as_str()) {
+                if resolved_at.elapsed() < self.ttl {
+                    return Ok(addrs.clone());
+                }
+            }
+            let addrs = self.inner.resolve(host.as_str()).await?;
+            if !addrs.is_empty() {
+                self.cache.lock().unwrap().insert(host, (Instant::now(), addrs.clone()));
+            }
+            Ok(addrs)
+        })
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct HttpResolverService {
+    overrides: Arc<HashMap<String, Vec<IpAddr>>>,
+    resolver: Arc<dyn HttpResolver>
+}
+
+impl HttpResolverService {
+    pub(crate) fn new(overrides: HashMap<String, Vec<IpAddr>>, resolver: Option<Arc<dyn HttpResolver>>) -> Self {
+        Self {
+            overrides: Arc::new(overrides),
+            resolver: resolver.unwrap_or_else(|| Arc::new(HttpSystemResolver))
+        }
+    }
+}
+
+impl tower_service::Service<Name> for HttpResolverService {
+    type Response = std::vec::IntoIter<IpAddr>;
+    type Error = io::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, name: Name) -> Self::Future {
+        let overrides = self.overrides.clone();
+        let resolver = self.resolver.clone();
+        Box::pin(async move {
+            let host = name.as_str().to_lowercase();
+            let addrs = match overrides.get(host.as_str()) {
+                Some(addrs) => addrs.clone(),
+                None => resolver.resolve(host.as_str()).await?
+            };
+            if addrs.is_empty() {
+                return Err(io::Error::new(io::ErrorKind::NotFound, format!("no address resolved for {}", host)));
+            }
+            log::debug!("resolved {} to {:?}", host, addrs);
+            Ok(addrs.into_iter())
+        })
+    }
+}
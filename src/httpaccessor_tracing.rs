@@ -0,0 +1,340 @@
+use std::any::TypeId;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use futures::Future;
+use rand::Rng;
+use tracing::{Event, Metadata, Subscriber};
+use tracing::span::{Attributes, Id, Record};
+use tracing_core::span::Current;
+use tracing::subscriber::Interest;
+use tracing::level_filters::LevelFilter;
+use crate::httpaccessor::HttpAccessorRequest;
+
+tokio::task_local! {
+    static CURRENT_TRACE_CONTEXT: HttpTraceContext;
+}
+
+thread_local! {
+    static ENTERED_SPANS: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+}
+
+const TRACE_PROPAGATION_HEADERS: [&str; 8] = [
+    "traceparent", "tracestate", "b3", "x-b3-traceid", "x-b3-spanid", "x-b3-parentspanid", "x-b3-sampled", "x-b3-flags"
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HttpTracePropagation {
+    W3C,
+    B3,
+    W3CAndB3
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpTraceContext {
+    pub trace_id: String,
+    pub span_id: String,
+    pub parent_span_id: Option<String>,
+    pub sampled: bool,
+    pub trace_state: Option<String>
+}
+
+fn random_hex(bytes: usize) -> String {
+    let mut rng = rand::thread_rng();
+    loop {
+        let hex: String = (0..bytes).map(|_| format!("{:02x}", rng.gen::<u8>())).collect();
+        if hex.chars().any(|c| c != '0') {
+            return hex;
+        }
+    }
+}
+
+fn is_hex_id(id: &str, len: usize) -> bool {
+    id.len() == len && id.chars().all(|c| c.is_ascii_hexdigit()) && id.chars().any(|c| c != '0')
+}
+
+fn find_header<'a>(headers: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    headers.iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .map(|(_, v)| v.trim())
+}
+
+impl HttpTraceContext {
+    pub fn new_root() -> Self {
+        Self {
+            trace_id: random_hex(16),
+            span_id: random_hex(8),
+            parent_span_id: None,
+            sampled: true,
+            trace_state: None
+        }
+    }
+
+    pub fn child(&self) -> Self {
+        Self {
+            trace_id: self.trace_id.clone(),
+            span_id: random_hex(8),
+            parent_span_id: Some(self.span_id.clone()),
+            sampled: self.sampled,
+            trace_state: self.trace_state.clone()
+        }
+    }
+
+    pub fn from_traceparent(traceparent: &str, trace_state: Option<&str>) -> Option<Self> {
+        let parts: Vec<&str> = traceparent.trim().split('-').collect();
+        if parts.len() < 4 || parts[0].len() != 2 || parts[0] == "ff" || !is_hex_id(parts[1], 32) || !is_hex_id(parts[2], 16) {
+            return None;
+        }
+        let flags = u8::from_str_radix(parts[3], 16).ok()?;
+        Some(Self {
+            trace_id: parts[1].to_lowercase(),
+            span_id: parts[2].to_lowercase(),
+            parent_span_id: None,
+            sampled: flags & 0x01 == 0x01,
+            trace_state: trace_state.map(|trace_state| trace_state.to_string()).filter(|trace_state| !trace_state.is_empty())
+        })
+    }
+
+    pub fn from_b3(headers: &[(String, String)]) -> Option<Self> {
+        if let Some(b3) = find_header(headers, "b3") {
+            let parts: Vec<&str> = b3.split('-').collect();
+            if parts.len() < 2 {
+                return None;
+            }
+            let trace_id = match parts[0].len() {
+                16 => format!("{:0>32}", parts[0]),
+                _ => parts[0].to_string()
+            };
+            if !is_hex_id(trace_id.as_str(), 32) || !is_hex_id(parts[1], 16) {
+                return None;
+            }
+            return Some(Self {
+                trace_id: trace_id.to_lowercase(),
+                span_id: parts[1].to_lowercase(),
+                parent_span_id: parts.get(3).map(|parent_span_id| parent_span_id.to_lowercase()),
+                sampled: parts.get(2).is_none_or(|sampled| *sampled == "1" || *sampled == "d"),
+                trace_state: None
+            });
+        }
+        let trace_id = find_header(headers, "x-b3-traceid")?;
+        let trace_id = match trace_id.len() {
+            16 => format!("{:0>32}", trace_id),
+            _ => trace_id.to_string()
+        };
+        let span_id = find_header(headers, "x-b3-spanid")?;
+        if !is_hex_id(trace_id.as_str(), 32) || !is_hex_id(span_id, 16) {
+            return None;
+        }
+        Some(Self {
+            trace_id: trace_id.to_lowercase(),
+            span_id: span_id.to_lowercase(),
+            parent_span_id: find_header(headers, "x-b3-parentspanid").map(|parent_span_id| parent_span_id.to_lowercase()),
+            sampled: find_header(headers, "x-b3-sampled").is_none_or(|sampled| sampled == "1" || sampled == "true")
+                || find_header(headers, "x-b3-flags") == Some("1"),
+            trace_state: None
+        })
+    }
+
+    pub fn from_headers(headers: &[(String, String)]) -> Option<Self> {
+        find_header(headers, "traceparent")
+            .and_then(|traceparent| Self::from_traceparent(traceparent, find_header(headers, "tracestate")))
+            .or_else(|| Self::from_b3(headers))
+    }
+
+    pub fn traceparent(&self) -> String {
+        format!("00-{}-{}-{}", self.trace_id, self.span_id, if self.sampled { "01" } else { "00" })
+    }
+
+    pub fn b3(&self) -> String {
+        let mut b3 = format!("{}-{}-{}", self.trace_id, self.span_id, if self.sampled { "1" } else { "0" });
+        if let Some(parent_span_id) = self.parent_span_id.as_ref() {
+            b3 = format!("{}-{}", b3, parent_span_id);
+        }
+        b3
+    }
+
+    // scope() wins, then the nearest context attached to the current tracing span or one of its parents; a span
+    // tree without one gets a single root context on its outermost span. Span lookup needs HttpTraceSubscriber
+    pub fn current() -> Option<Self> {
+        CURRENT_TRACE_CONTEXT.try_with(|trace_context| trace_context.clone()).ok()
+            .or_else(|| tracing::dispatcher::get_default(|dispatch| {
+                dispatch.downcast_ref::<HttpTraceSpans>().and_then(|spans| spans.current_context())
+            }))
+    }
+
+    pub fn attach(&self, span: &tracing::Span) {
+        span.with_subscriber(|(id, dispatch)| {
+            if let Some(spans) = dispatch.downcast_ref::<HttpTraceSpans>() {
+                spans.attach(id, self.clone());
+            }
+        });
+    }
+
+    pub async fn scope<F: Future>(self, f: F) -> F::Output {
+        CURRENT_TRACE_CONTEXT.scope(self, f).await
+    }
+
+    pub(crate) fn inject(&self, propagation: HttpTracePropagation, request: &mut HttpAccessorRequest) {
+        request.headers.retain(|(key, _)| !TRACE_PROPAGATION_HEADERS.iter().any(|header| key.eq_ignore_ascii_case(header)));
+        if propagation == HttpTracePropagation::W3C || propagation == HttpTracePropagation::W3CAndB3 {
+            request.headers.push(("traceparent".to_string(), self.traceparent()));
+            if let Some(trace_state) = self.trace_state.as_ref() {
+                request.headers.push(("tracestate".to_string(), trace_state.clone()));
+            }
+        }
+        if propagation == HttpTracePropagation::B3 || propagation == HttpTracePropagation::W3CAndB3 {
+            request.headers.push(("b3".to_string(), self.b3()));
+        }
+    }
+}
+
+struct HttpTraceSpan {
+    metadata: &'static Metadata<'static>,
+    parent: Option<u64>,
+    context: Option<HttpTraceContext>,
+    refs: usize
+}
+
+pub(crate) struct HttpTraceSpans {
+    spans: Mutex<HashMap<u64, HttpTraceSpan>>
+}
+
+impl HttpTraceSpans {
+    fn current_id() -> Option<u64> {
+        ENTERED_SPANS.with(|entered| entered.borrow().last().copied())
+    }
+
+    fn attach(&self, id: &Id, trace_context: HttpTraceContext) {
+        if let Some(span) = self.spans.lock().unwrap().get_mut(&id.into_u64()) {
+            span.context = Some(trace_context);
+        }
+    }
+
+    fn current_context(&self) -> Option<HttpTraceContext> {
+        let mut spans = self.spans.lock().unwrap();
+        let mut id = Self::current_id()?;
+        loop {
+            let span = spans.get(&id)?;
+            if let Some(trace_context) = span.context.as_ref() {
+                return Some(trace_context.clone());
+            }
+            match span.parent.filter(|parent| spans.contains_key(parent)) {
+                Some(parent) => id = parent,
+                None => break
+            }
+        }
+        let trace_context = HttpTraceContext::new_root();
+        spans.get_mut(&id)?.context = Some(trace_context.clone());
+        Some(trace_context)
+    }
+}
+
+pub struct HttpTraceSubscriber<S> {
+    inner: S,
+    spans: HttpTraceSpans
+}
+
+impl<S: Subscriber> HttpTraceSubscriber<S> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            spans: HttpTraceSpans {
+                spans: Mutex::new(HashMap::new())
+            }
+        }
+    }
+}
+
+impl<S: Subscriber> Subscriber for HttpTraceSubscriber<S> {
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        self.inner.register_callsite(metadata)
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        self.inner.max_level_hint()
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let id = self.inner.new_span(span);
+        let parent = match span.parent() {
+            Some(parent) => Some(parent.into_u64()),
+            None if span.is_contextual() => HttpTraceSpans::current_id(),
+            None => None
+        };
+        self.spans.spans.lock().unwrap().insert(id.into_u64(), HttpTraceSpan {
+            metadata: span.metadata(),
+            parent,
+            context: None,
+            refs: 1
+        });
+        id
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        self.inner.record(span, values)
+    }
+
+    fn record_follows_from(&self, span: &Id, follows: &Id) {
+        self.inner.record_follows_from(span, follows)
+    }
+
+    fn event(&self, event: &Event<'_>) {
+        self.inner.event(event)
+    }
+
+    fn enter(&self, span: &Id) {
+        ENTERED_SPANS.with(|entered| entered.borrow_mut().push(span.into_u64()));
+        self.inner.enter(span)
+    }
+
+    fn exit(&self, span: &Id) {
+        ENTERED_SPANS.with(|entered| {
+            let mut entered = entered.borrow_mut();
+            if let Some(pos) = entered.iter().rposition(|id| *id == span.into_u64()) {
+                entered.remove(pos);
+            }
+        });
+        self.inner.exit(span)
+    }
+
+    fn clone_span(&self, id: &Id) -> Id {
+        if let Some(span) = self.spans.spans.lock().unwrap().get_mut(&id.into_u64()) {
+            span.refs += 1;
+        }
+        self.inner.clone_span(id)
+    }
+
+    fn try_close(&self, id: Id) -> bool {
+        let mut spans = self.spans.spans.lock().unwrap();
+        if let Some(span) = spans.get_mut(&id.into_u64()) {
+            span.refs -= 1;
+            if span.refs == 0 {
+                spans.remove(&id.into_u64());
+            }
+        }
+        drop(spans);
+        self.inner.try_close(id)
+    }
+
+    fn current_span(&self) -> Current {
+        let spans = self.spans.spans.lock().unwrap();
+        match HttpTraceSpans::current_id().and_then(|id| spans.get(&id).map(|span| (id, span.metadata))) {
+            Some((id, metadata)) => Current::new(Id::from_u64(id), metadata),
+            None => self.inner.current_span()
+        }
+    }
+
+    unsafe fn downcast_raw(&self, id: TypeId) -> Option<*const ()> {
+        if id == TypeId::of::<Self>() {
+            Some(self as *const Self as *const ())
+        } else if id == TypeId::of::<HttpTraceSpans>() {
+            Some(&self.spans as *const HttpTraceSpans as *const ())
+        } else {
+            self.inner.downcast_raw(id)
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use tokio::time::Duration;
+use futures::future::BoxFuture;
+use crate::httpaccessor::{HttpAccessor, HttpAccessorError, HttpAccessorErrorType, HttpAccessorRequest, HttpAccessorResponse, HttpAccessorResponseError};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HttpTransportErrorKind {
+    Connect,
+    Timeout,
+    Other
+}
+
+pub trait HttpTransport: Send + Sync {
+    fn send(&self, request: HttpAccessorRequest) -> BoxFuture<'_, Result<HttpAccessorResponse, HttpAccessorResponseError>>;
+}
+
+pub struct ReqwestTransport {
+    client: reqwest::Client
+}
+
+impl ReqwestTransport {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self {
+            client
+        }
+    }
+}
+
+impl HttpTransport for ReqwestTransport {
+    fn send(&self, request: HttpAccessorRequest) -> BoxFuture<'_, Result<HttpAccessorResponse, HttpAccessorResponseError>> {
+        let mut request_builder = self.client
+            .request(request.method.clone(), request.url.as_str());
+        if let Some(timeout) = request.timeout {
+            request_builder = request_builder.timeout(Duration::from_secs(timeout as u64));
+        }
+        for (key, val) in request.headers.iter() {
+            request_builder = request_builder.header(key.as_str(), val.as_str());
+        }
+        if let Some(body) = request.body {
+            request_builder = request_builder.body(body);
+        }
+        let url = request.url;
+        Box::pin(async move {
+            let resp = request_builder
+                .send()
+                .await
+                .map_err(|e| HttpAccessorResponseError {
+                    url: url.clone(),
+                    status_code: None,
+                    err: Some(HttpAccessorError {
+                        err_type: HttpAccessorErrorType::OpenUrlError(e),
+                        msg: "async send fail".to_string()
+                    })
+                })?;
+            HttpAccessor::handle_async_resp(url.as_str(), resp).await
+        })
+    }
+}
+
+pub(crate) struct AccessorTransport {
+    http_accessor: HttpAccessor
+}
+
+impl AccessorTransport {
+    pub(crate) fn new(http_accessor: HttpAccessor) -> Self {
+        Self {
+            http_accessor
+        }
+    }
+}
+
+impl HttpTransport for AccessorTransport {
+    fn send(&self, request: HttpAccessorRequest) -> BoxFuture<'_, Result<HttpAccessorResponse, HttpAccessorResponseError>> {
+        Box::pin(async move {
+            let transport = self.http_accessor.get_transport().map_err(|e| HttpAccessorResponseError {
+                url: request.url().to_string(),
+                status_code: None,
+                err: Some(e)
+            })?;
+            transport.send(request).await
+        })
+    }
+}
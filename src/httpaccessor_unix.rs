@@ -0,0 +1,75 @@
+use std::path::PathBuf;
+use tokio::time::Duration;
+use futures::future::BoxFuture;
+use crate::httpaccessor::{HttpAccessorError, HttpAccessorRequest, HttpAccessorResponse, HttpAccessorResponseError};
+use crate::httpaccessor_transport::{HttpTransport, HttpTransportErrorKind};
+use crate::httpaccessor_hyper::{build_hyper_request, handle_hyper_resp};
+
+const UNIX_SOCKET_DEFAULT_HOST: &str = "localhost";
+
+pub struct UnixSocketTransport {
+    socket_path: PathBuf
+}
+
+fn split_url(url: &str) -> (String, String) {
+    let without_scheme = match url.find("://") {
+        Some(pos) => &url[pos + 3..],
+        None => url
+    };
+    if without_scheme.starts_with('/') {
+        return (UNIX_SOCKET_DEFAULT_HOST.to_string(), without_scheme.to_string());
+    }
+    match without_scheme.find('/') {
+        Some(pos) => (without_scheme[..pos].to_string(), without_scheme[pos..].to_string()),
+        None => (without_scheme.to_string(), "/".to_string())
+    }
+}
+
+impl UnixSocketTransport {
+    pub fn new(socket_path: &str) -> Self {
+        Self {
+            socket_path: PathBuf::from(socket_path)
+        }
+    }
+
+    async fn send_request(&self, request: HttpAccessorRequest) -> Result<HttpAccessorResponse, HttpAccessorResponseError> {
+        let url = request.url().to_string();
+        let gen_err = |status_code: Option<u16>, kind: HttpTransportErrorKind, msg: String| HttpAccessorResponseError {
+            url: url.clone(),
+            status_code,
+            err: Some(HttpAccessorError::transport(kind, msg.as_str()))
+        };
+        let stream = tokio::net::UnixStream::connect(&self.socket_path).await
+            .map_err(|e| gen_err(None, HttpTransportErrorKind::Connect, format!("connect {} fail: {}", self.socket_path.display(), e)))?;
+        let (mut sender, conn) = hyper::client::conn::handshake(stream).await
+            .map_err(|e| gen_err(None, HttpTransportErrorKind::Connect, format!("http handshake fail: {}", e)))?;
+        tokio::spawn(async move {
+            if let Err(e) = conn.await {
+                log::debug!("unix socket connection closed: {}", e);
+            }
+        });
+        let (host, path) = split_url(url.as_str());
+        let hyper_request = build_hyper_request(&request, path.as_str(), Some(host.as_str()))
+            .map_err(|msg| gen_err(None, HttpTransportErrorKind::Other, msg))?;
+        let resp = sender.send_request(hyper_request).await
+            .map_err(|e| gen_err(None, HttpTransportErrorKind::Other, format!("send request fail: {}", e)))?;
+        handle_hyper_resp(url.as_str(), resp).await
+    }
+}
+
+impl HttpTransport for UnixSocketTransport {
+    fn send(&self, request: HttpAccessorRequest) -> BoxFuture<'_, Result<HttpAccessorResponse, HttpAccessorResponseError>> {
+        Box::pin(async move {
+            let url = request.url().to_string();
+            match request.timeout_secs() {
+                Some(timeout) => tokio::time::timeout(Duration::from_secs(timeout as u64), self.send_request(request)).await
+                    .unwrap_or_else(|_| Err(HttpAccessorResponseError {
+                        url,
+                        status_code: None,
+                        err: Some(HttpAccessorError::transport(HttpTransportErrorKind::Timeout, "unix socket request timeout"))
+                    })),
+                None => self.send_request(request).await
+            }
+        })
+    }
+}
@@ -0,0 +1,186 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use rand::Rng;
+
+const CONSISTENT_HASH_VIRTUAL_NODES: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HttpAccessorBalanceStrategy {
+    RoundRobin,
+    Random,
+    LeastOutstanding,
+    ConsistentHash
+}
+
+struct UpstreamInstance {
+    base_url: String,
+    outstanding: AtomicUsize,
+    consecutive_fails: AtomicU32,
+    ejected_until: Mutex<Option<Instant>>
+}
+
+#[derive(Clone)]
+pub struct HttpAccessorUpstream {
+    instances: Arc<Vec<UpstreamInstance>>,
+    ring: Arc<Vec<(u64, usize)>>,
+    counter: Arc<AtomicUsize>,
+    pub(crate) strategy: HttpAccessorBalanceStrategy,
+    pub(crate) max_fails: u32,
+    pub(crate) fail_timeout: u32
+}
+
+pub(crate) struct UpstreamOutstandingGuard<'a> {
+    instance: &'a UpstreamInstance
+}
+
+impl<'a> Drop for UpstreamOutstandingGuard<'a> {
+    fn drop(&mut self) {
+        self.instance.outstanding.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+fn hash_key(key: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl HttpAccessorUpstream {
+    pub fn new(base_urls: &[&str]) -> Self {
+        let instances: Vec<UpstreamInstance> = base_urls.iter().map(|base_url| UpstreamInstance {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            outstanding: AtomicUsize::new(0),
+            consecutive_fails: AtomicU32::new(0),
+            ejected_until: Mutex::new(None)
+        }).collect();
+        let mut ring = Vec::with_capacity(instances.len() * CONSISTENT_HASH_VIRTUAL_NODES);
+        for (idx, instance) in instances.iter().enumerate() {
+            for vnode in 0..CONSISTENT_HASH_VIRTUAL_NODES {
+                ring.push((hash_key(format!("{}#{}", instance.base_url, vnode).as_str()), idx));
+            }
+        }
+        ring.sort();
+        Self {
+            instances: Arc::new(instances),
+            ring: Arc::new(ring),
+            counter: Arc::new(AtomicUsize::new(0)),
+            strategy: HttpAccessorBalanceStrategy::RoundRobin,
+            max_fails: 3,
+            fail_timeout: 30
+        }
+    }
+
+    pub fn strategy(mut self, strategy: HttpAccessorBalanceStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    pub fn max_fails(mut self, max_fails: u32) -> Self {
+        self.max_fails = max_fails;
+        self
+    }
+
+    pub fn fail_timeout(mut self, fail_timeout: u32) -> Self {
+        self.fail_timeout = fail_timeout;
+        self
+    }
+
+    pub fn base_urls(&self) -> Vec<&str> {
+        self.instances.iter().map(|instance| instance.base_url.as_str()).collect()
+    }
+
+    pub fn healthy_base_urls(&self) -> Vec<&str> {
+        self.instances.iter()
+            .filter(|instance| !Self::is_ejected(instance))
+            .map(|instance| instance.base_url.as_str())
+            .collect()
+    }
+
+    fn is_ejected(instance: &UpstreamInstance) -> bool {
+        let mut ejected_until = instance.ejected_until.lock().unwrap();
+        match *ejected_until {
+            Some(until) if until > Instant::now() => true,
+            Some(_) => {
+                *ejected_until = None;
+                false
+            },
+            None => false
+        }
+    }
+
+    fn pick(&self, key: &str) -> usize {
+        let len = self.instances.len();
+        match self.strategy {
+            HttpAccessorBalanceStrategy::RoundRobin => self.counter.fetch_add(1, Ordering::SeqCst) % len,
+            HttpAccessorBalanceStrategy::Random => rand::thread_rng().gen_range(0, len),
+            HttpAccessorBalanceStrategy::LeastOutstanding => {
+                let start = self.counter.fetch_add(1, Ordering::SeqCst) % len;
+                (0..len).map(|offset| (start + offset) % len)
+                    .filter(|idx| !Self::is_ejected(&self.instances[*idx]))
+                    .min_by_key(|idx| self.instances[*idx].outstanding.load(Ordering::SeqCst))
+                    .unwrap_or(start)
+            },
+            HttpAccessorBalanceStrategy::ConsistentHash => {
+                let hash = hash_key(key);
+                let pos = match self.ring.binary_search_by(|(node_hash, _)| node_hash.cmp(&hash)) {
+                    Ok(pos) => pos,
+                    Err(pos) => pos % self.ring.len()
+                };
+                self.ring[pos].1
+            }
+        }
+    }
+
+    pub(crate) fn candidates(&self, key: &str) -> Vec<usize> {
+        let len = self.instances.len();
+        if len == 0 {
+            return Vec::new();
+        }
+        let first = self.pick(key);
+        let ordered: Vec<usize> = (0..len).map(|offset| (first + offset) % len).collect();
+        let healthy: Vec<usize> = ordered.iter()
+            .filter(|idx| !Self::is_ejected(&self.instances[**idx]))
+            .cloned()
+            .collect();
+        if healthy.is_empty() {
+            ordered
+        } else {
+            healthy
+        }
+    }
+
+    pub(crate) fn join_url(&self, idx: usize, path: &str) -> String {
+        let base_url = self.instances[idx].base_url.as_str();
+        if path.is_empty() {
+            base_url.to_string()
+        } else if path.starts_with('/') {
+            format!("{}{}", base_url, path)
+        } else {
+            format!("{}/{}", base_url, path)
+        }
+    }
+
+    pub(crate) fn acquire(&self, idx: usize) -> UpstreamOutstandingGuard<'_> {
+        let instance = &self.instances[idx];
+        instance.outstanding.fetch_add(1, Ordering::SeqCst);
+        UpstreamOutstandingGuard {
+            instance
+        }
+    }
+
+    pub(crate) fn report_success(&self, idx: usize) {
+        self.instances[idx].consecutive_fails.store(0, Ordering::SeqCst);
+    }
+
+    pub(crate) fn report_failure(&self, idx: usize) {
+        let instance = &self.instances[idx];
+        let fails = instance.consecutive_fails.fetch_add(1, Ordering::SeqCst) + 1;
+        if self.max_fails > 0 && fails >= self.max_fails {
+            instance.consecutive_fails.store(0, Ordering::SeqCst);
+            *instance.ejected_until.lock().unwrap() = Some(Instant::now() + Duration::from_secs(self.fail_timeout as u64));
+        }
+    }
+}
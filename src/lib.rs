@@ -8,15 +8,49 @@ mod tests {
 
 #[cfg(any(feature="mysql_async", feature="mysql_pool_async"))]
 pub mod mysqlaccessor;
+#[cfg(any(feature="mysql_async", feature="mysql_pool_async"))]
+pub mod mysqlaccessor_transaction;
+#[cfg(any(feature="mysql_async", feature="mysql_pool_async"))]
+pub mod mysqlaccessor_batch;
 #[cfg(feature="mysql_async")]
 pub mod mysqlaccessor_async;
 #[cfg(feature="mysql_pool_async")]
 pub mod mysqlaccessor_pool_async;
+#[cfg(feature="mysql_pool_async")]
+pub mod mysqlaccessor_routed_async;
 #[cfg(feature="http_async")]
 pub mod httpaccessor;
+#[cfg(feature="http_async")]
+pub mod httpaccessor_upstream;
+#[cfg(feature="http_async")]
+pub mod httpaccessor_transport;
+#[cfg(feature="http_async")]
+pub mod httpaccessor_mock;
+#[cfg(feature="http_async")]
+pub mod httpaccessor_cassette;
+#[cfg(feature="http_async")]
+pub mod httpaccessor_graphql;
+#[cfg(feature="http_async")]
+pub mod httpaccessor_jsonrpc;
+#[cfg(feature="http_async")]
+pub mod httpaccessor_pagination;
+#[cfg(feature="http_async")]
+pub mod httpaccessor_tracing;
+#[cfg(feature="http_async")]
+pub mod httpaccessor_ratelimit;
+#[cfg(feature="http_async")]
+pub mod httpaccessor_compression;
+#[cfg(all(unix, feature="http_async"))]
+pub mod httpaccessor_unix;
+#[cfg(feature="http_async")]
+pub mod httpaccessor_hyper;
+#[cfg(feature="http_async")]
+pub mod httpaccessor_resolver;
 #[cfg(any(feature="redis_async", feature="redis_actix"))]
 pub mod redisaccessor;
 #[cfg(feature="redis_async")]
 pub mod redisaccessor_async;
 #[cfg(feature="redis_actix")]
 pub mod redisaccessor_actix;
+#[cfg(feature="ws_async")]
+pub mod wsaccessor;
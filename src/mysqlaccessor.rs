@@ -1,3 +1,13 @@
+use sqlx::mysql::{MySql, MySqlArguments, MySqlConnectOptions, MySqlConnection, MySqlDatabaseError, MySqlRow, MySqlSslMode};
+use sqlx::{Column, Connection, Done, Row};
+use sqlx::query::Query;
+use sqlx::types::chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use sqlx::types::Decimal;
+use futures::{StreamExt, TryStreamExt};
+use futures::stream::BoxStream;
+use futures::Future;
+use tokio::time::Duration;
+
 #[derive(Debug)]
 pub struct MySQLAccessorError {
     pub err_type: MySQLAccessorErrorType
@@ -7,9 +17,563 @@ pub struct MySQLAccessorError {
 pub enum MySQLAccessorErrorType {
     ConnNotOpen,
     OpenConnError(sqlx::Error),
+    AcquireConnError(sqlx::Error),
     SqlSelectError(sqlx::Error),
     SqlFetchRowError(sqlx::Error),
     SqlFetchColumnError(sqlx::Error),
+    SqlExecuteError(sqlx::Error),
+    TransactionError(sqlx::Error),
+    SqlRowNotFound,
+    RowDecodeError(String, sqlx::Error),
+    InvalidBatchRow(usize),
+    InvalidBatchColumns,
+    PingError(sqlx::Error),
+}
+
+const MYSQL_ER_LOCK_DEADLOCK: u16 = 1213;
+const MYSQL_MAX_RETRY_INTERVAL: u64 = 30000;
+const MYSQL_CONNECTION_ERRORS: [u16; 5] = [1053, 1927, 2006, 2013, 4031];
+const MYSQL_IDEMPOTENT_STATEMENTS: [&str; 5] = ["SELECT", "SHOW", "DESCRIBE", "DESC", "EXPLAIN"];
+const MYSQL_LOCKING_READ_CLAUSES: [&str; 6] = ["FOR UPDATE", "FOR SHARE", "LOCK IN SHARE MODE", "GET_LOCK", "RELEASE_LOCK", "RELEASE_ALL_LOCKS"];
+const MYSQL_SIDE_EFFECT_CLAUSES: [&str; 3] = ["INTO @", "INTO OUTFILE", "INTO DUMPFILE"];
+
+impl MySQLAccessorError {
+    pub(crate) fn sqlx_error(&self) -> Option<&sqlx::Error> {
+        match &self.err_type {
+            MySQLAccessorErrorType::OpenConnError(e)
+            | MySQLAccessorErrorType::AcquireConnError(e)
+            | MySQLAccessorErrorType::SqlSelectError(e)
+            | MySQLAccessorErrorType::SqlFetchRowError(e)
+            | MySQLAccessorErrorType::SqlFetchColumnError(e)
+            | MySQLAccessorErrorType::SqlExecuteError(e)
+            | MySQLAccessorErrorType::TransactionError(e)
+            | MySQLAccessorErrorType::PingError(e)
+            | MySQLAccessorErrorType::RowDecodeError(_, e) => Some(e),
+            MySQLAccessorErrorType::ConnNotOpen
+            | MySQLAccessorErrorType::SqlRowNotFound
+            | MySQLAccessorErrorType::InvalidBatchRow(_)
+            | MySQLAccessorErrorType::InvalidBatchColumns => None
+        }
+    }
+
+    pub fn error_number(&self) -> Option<u16> {
+        self.sqlx_error()
+            .and_then(|e| e.as_database_error())
+            .and_then(|e| e.try_downcast_ref::<MySqlDatabaseError>())
+            .map(|e| e.number())
+    }
+
+    pub fn is_deadlock(&self) -> bool {
+        self.error_number() == Some(MYSQL_ER_LOCK_DEADLOCK)
+    }
+
+    pub fn is_connection_error(&self) -> bool {
+        match self.sqlx_error() {
+            Some(sqlx::Error::Io(_)) | Some(sqlx::Error::Tls(_)) => true,
+            Some(sqlx::Error::Database(_)) => self.error_number().is_some_and(|number| MYSQL_CONNECTION_ERRORS.contains(&number)),
+            _ => false
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MySQLSslMode {
+    Disabled,
+    Preferred,
+    Required,
+    VerifyCa,
+    VerifyIdentity
+}
+
+impl MySQLSslMode {
+    pub(crate) fn to_sqlx(self) -> MySqlSslMode {
+        match self {
+            MySQLSslMode::Disabled => MySqlSslMode::Disabled,
+            MySQLSslMode::Preferred => MySqlSslMode::Preferred,
+            MySQLSslMode::Required => MySqlSslMode::Required,
+            MySQLSslMode::VerifyCa => MySqlSslMode::VerifyCa,
+            MySQLSslMode::VerifyIdentity => MySqlSslMode::VerifyIdentity
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MySQLConnectConfig {
+    pub(crate) host: String,
+    pub(crate) port: u16,
+    pub(crate) user: String,
+    pub(crate) passwd: String,
+    pub(crate) db: String,
+    pub(crate) charset: String,
+    pub(crate) url: Option<String>,
+    pub(crate) socket: Option<String>,
+    pub(crate) ssl_mode: Option<MySQLSslMode>,
+    pub(crate) ssl_ca: Option<String>,
+    pub(crate) collation: Option<String>,
+    pub(crate) time_zone: Option<String>,
+    pub(crate) statement_cache_capacity: Option<usize>
+}
+
+impl Default for MySQLConnectConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MySQLConnectConfig {
+    pub fn new() -> Self {
+        Self {
+            host: String::from("localhost"),
+            port: 3308,
+            user: String::from("root"),
+            passwd: String::from(""),
+            db: String::from(""),
+            charset: String::from("utf8"),
+            url: None,
+            socket: None,
+            ssl_mode: None,
+            ssl_ca: None,
+            collation: None,
+            time_zone: None,
+            statement_cache_capacity: None
+        }
+    }
+
+    pub fn host(mut self, host: &str) -> Self {
+        self.host = host.to_string();
+        self
+    }
+
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    pub fn user(mut self, user: &str) -> Self {
+        self.user = user.to_string();
+        self
+    }
+
+    pub fn passwd(mut self, passwd: &str) -> Self {
+        self.passwd = passwd.to_string();
+        self
+    }
+
+    pub fn db(mut self, db: &str) -> Self {
+        self.db = db.to_string();
+        self
+    }
+
+    pub fn charset(mut self, charset: &str) -> Self {
+        self.charset = charset.to_string();
+        self
+    }
+
+    pub fn url(mut self, url: &str) -> Self {
+        self.url = Some(url.to_string());
+        self
+    }
+
+    pub fn socket(mut self, socket: &str) -> Self {
+        self.socket = Some(socket.to_string());
+        self
+    }
+
+    pub fn ssl_mode(mut self, ssl_mode: MySQLSslMode) -> Self {
+        self.ssl_mode = Some(ssl_mode);
+        self
+    }
+
+    pub fn ssl_ca(mut self, ssl_ca: &str) -> Self {
+        self.ssl_ca = Some(ssl_ca.to_string());
+        self
+    }
+
+    pub fn collation(mut self, collation: &str) -> Self {
+        self.collation = Some(collation.to_string());
+        self
+    }
+
+    /// Runs `SET time_zone = ?` on every new connection. sqlx binds and decodes `DateTime<Utc>` as a
+    /// naive value in the session zone, so with a non-UTC zone TIMESTAMP columns round-trip unchanged
+    /// but no longer match `NOW()` or `UNIX_TIMESTAMP()`; use DATETIME with `NaiveDateTime` or
+    /// `CONVERT_TZ` when the wall-clock value matters.
+    pub fn time_zone(mut self, time_zone: &str) -> Self {
+        self.time_zone = Some(time_zone.to_string());
+        self
+    }
+
+    pub fn statement_cache_capacity(mut self, statement_cache_capacity: usize) -> Self {
+        self.statement_cache_capacity = Some(statement_cache_capacity);
+        self
+    }
+
+    pub(crate) fn get_dsn(&self) -> String {
+        match self.url.as_deref() {
+            Some(url) => redacted_url(url),
+            None => redacted_dsn(&self.user, &self.host, self.port, &self.db)
+        }
+    }
+
+    pub(crate) fn get_connect_option(&self) -> Result<MySqlConnectOptions, MySQLAccessorError> {
+        let mut connect_options = match self.url.as_deref() {
+            Some(url) => url.parse::<MySqlConnectOptions>().map_err(open_conn_err)?,
+            None => MySqlConnectOptions::new()
+                .username(&self.user)
+                .password(&self.passwd)
+                .host(&self.host)
+                .port(self.port)
+                .database(&self.db)
+                .charset(&self.charset)
+        };
+        if let Some(socket) = self.socket.as_deref() {
+            connect_options = connect_options.socket(socket);
+        }
+        if let Some(ssl_mode) = self.ssl_mode {
+            connect_options = connect_options.ssl_mode(ssl_mode.to_sqlx());
+        }
+        if let Some(ssl_ca) = self.ssl_ca.as_deref() {
+            connect_options = connect_options.ssl_ca(ssl_ca);
+        }
+        if let Some(collation) = self.collation.as_deref() {
+            connect_options = connect_options.collation(collation);
+        }
+        if let Some(statement_cache_capacity) = self.statement_cache_capacity {
+            connect_options = connect_options.statement_cache_capacity(statement_cache_capacity);
+        }
+        Ok(connect_options)
+    }
+
+    pub(crate) async fn connect(&self, connect_options: &MySqlConnectOptions) -> Result<MySqlConnection, sqlx::Error> {
+        let mut conn = MySqlConnection::connect_with(connect_options).await?;
+        init_session(&mut conn, self.time_zone.as_deref()).await?;
+        Ok(conn)
+    }
+}
+
+pub(crate) async fn init_session(conn: &mut MySqlConnection, time_zone: Option<&str>) -> Result<(), sqlx::Error> {
+    if let Some(time_zone) = time_zone {
+        sqlx::query("SET time_zone = ?").bind(time_zone).execute(conn).await?;
+    }
+    Ok(())
+}
+
+macro_rules! delegate_connect_config {
+    ($($name:ident: $ty:ty),* $(,)?) => {
+        $(
+            pub fn $name(mut self, $name: $ty) -> Self {
+                self.connect_config = self.connect_config.$name($name);
+                self
+            }
+        )*
+    };
+}
+
+pub(crate) use delegate_connect_config;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MySQLValue {
+    Null,
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Decimal(Decimal),
+    String(String),
+    Bytes(Vec<u8>),
+    Date(NaiveDate),
+    Time(NaiveTime),
+    DateTime(NaiveDateTime),
+    Timestamp(DateTime<Utc>)
+}
+
+macro_rules! impl_mysql_value_from {
+    ($variant:ident, $target:ty, $($from:ty),+) => {
+        $(
+            impl From<$from> for MySQLValue {
+                fn from(val: $from) -> Self {
+                    MySQLValue::$variant(val as $target)
+                }
+            }
+        )+
+    };
+}
+
+impl_mysql_value_from!(Int, i64, i8, i16, i32, i64);
+impl_mysql_value_from!(UInt, u64, u8, u16, u32, u64);
+impl_mysql_value_from!(Float, f64, f32, f64);
+
+impl From<bool> for MySQLValue {
+    fn from(val: bool) -> Self {
+        MySQLValue::Bool(val)
+    }
+}
+
+impl From<Decimal> for MySQLValue {
+    fn from(val: Decimal) -> Self {
+        MySQLValue::Decimal(val)
+    }
+}
+
+impl From<&str> for MySQLValue {
+    fn from(val: &str) -> Self {
+        MySQLValue::String(val.to_string())
+    }
+}
+
+impl From<String> for MySQLValue {
+    fn from(val: String) -> Self {
+        MySQLValue::String(val)
+    }
+}
+
+impl From<&[u8]> for MySQLValue {
+    fn from(val: &[u8]) -> Self {
+        MySQLValue::Bytes(val.to_vec())
+    }
+}
+
+impl From<Vec<u8>> for MySQLValue {
+    fn from(val: Vec<u8>) -> Self {
+        MySQLValue::Bytes(val)
+    }
+}
+
+impl From<NaiveDate> for MySQLValue {
+    fn from(val: NaiveDate) -> Self {
+        MySQLValue::Date(val)
+    }
+}
+
+impl From<NaiveTime> for MySQLValue {
+    fn from(val: NaiveTime) -> Self {
+        MySQLValue::Time(val)
+    }
+}
+
+impl From<NaiveDateTime> for MySQLValue {
+    fn from(val: NaiveDateTime) -> Self {
+        MySQLValue::DateTime(val)
+    }
+}
+
+impl From<DateTime<Utc>> for MySQLValue {
+    fn from(val: DateTime<Utc>) -> Self {
+        MySQLValue::Timestamp(val)
+    }
+}
+
+impl<T: Into<MySQLValue>> From<Option<T>> for MySQLValue {
+    fn from(val: Option<T>) -> Self {
+        match val {
+            Some(val) => val.into(),
+            None => MySQLValue::Null
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! mysql_params {
+    ($($val:expr),* $(,)?) => {
+        <[$crate::mysqlaccessor::MySQLValue]>::into_vec(::std::boxed::Box::new([$($crate::mysqlaccessor::MySQLValue::from($val)),*]))
+    };
+}
+
+fn normalize_sql(sql: &str) -> String {
+    sql.split_whitespace().collect::<Vec<&str>>().join(" ").to_uppercase()
+}
+
+pub(crate) fn is_locking_read(sql: &str) -> bool {
+    let sql = normalize_sql(sql);
+    MYSQL_LOCKING_READ_CLAUSES.iter().any(|clause| sql.contains(clause))
+}
+
+pub(crate) fn is_idempotent_sql(sql: &str) -> bool {
+    let keyword: String = sql.trim_start_matches(|c: char| c.is_whitespace() || c == '(')
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect();
+    if !MYSQL_IDEMPOTENT_STATEMENTS.iter().any(|statement| keyword.eq_ignore_ascii_case(statement)) {
+        return false;
+    }
+    let normalized = normalize_sql(sql);
+    !is_locking_read(sql) && !MYSQL_SIDE_EFFECT_CLAUSES.iter().any(|clause| normalized.contains(clause))
+}
+
+pub(crate) fn transaction_state_change(sql: &str) -> Option<bool> {
+    let normalized = normalize_sql(sql);
+    let words: Vec<&str> = normalized.split(&[' ', ';'][..]).filter(|word| !word.is_empty()).take(3).collect();
+    match words.as_slice() {
+        ["BEGIN", ..] | ["START", "TRANSACTION", ..] => Some(true),
+        ["ROLLBACK", "TO", ..] | ["ROLLBACK", "WORK", "TO"] => None,
+        ["COMMIT", ..] | ["ROLLBACK", ..] => Some(false),
+        _ => None
+    }
+}
+
+pub(crate) fn redacted_dsn(user: &str, host: &str, port: u16, db: &str) -> String {
+    format!("mysql://{}:***@{}:{}/{}", user, host, port, db)
+}
+
+pub(crate) fn redacted_url(url: &str) -> String {
+    let scheme_end = url.find("://").map_or(0, |pos| pos + 3);
+    match url[scheme_end..].find('@') {
+        Some(at) => {
+            let userinfo = &url[scheme_end..scheme_end + at];
+            match userinfo.find(':') {
+                Some(colon) => format!("{}{}:***{}", &url[..scheme_end], &userinfo[..colon], &url[scheme_end + at..]),
+                None => url.to_string()
+            }
+        },
+        None => url.to_string()
+    }
+}
+
+pub(crate) fn open_conn_err(e: sqlx::Error) -> MySQLAccessorError {
+    MySQLAccessorError { err_type: MySQLAccessorErrorType::OpenConnError(e) }
+}
+
+pub(crate) async fn connect_with_retry<T, F, Fut>(dsn: &str, retries: u32, retry_interval: u64, mut connect: F) -> Result<T, MySQLAccessorError>
+    where F: FnMut() -> Fut,
+          Fut: Future<Output = Result<T, sqlx::Error>> {
+    let mut attempt = 0;
+    let mut interval = retry_interval;
+    loop {
+        match connect().await {
+            Ok(conn) => {
+                log::info!("connected to {}", dsn);
+                return Ok(conn);
+            },
+            Err(e) if attempt < retries => {
+                attempt += 1;
+                log::warn!("connect to {} fail: {}, retry {}/{} in {}ms", dsn, e, attempt, retries, interval);
+                tokio::time::delay_for(Duration::from_millis(interval)).await;
+                interval = std::cmp::min(interval.saturating_mul(2), MYSQL_MAX_RETRY_INTERVAL);
+            },
+            Err(e) => {
+                log::error!("connect to {} fail: {}", dsn, e);
+                return Err(open_conn_err(e));
+            }
+        }
+    }
+}
+
+pub(crate) fn build_query<'q>(sql: &'q str, params: &[MySQLValue]) -> Query<'q, MySql, MySqlArguments> {
+    params.iter().fold(sqlx::query(sql), |query, param| match param.clone() {
+        MySQLValue::Null => query.bind(Option::<String>::None),
+        MySQLValue::Bool(val) => query.bind(val),
+        MySQLValue::Int(val) => query.bind(val),
+        MySQLValue::UInt(val) => query.bind(val),
+        MySQLValue::Float(val) => query.bind(val),
+        MySQLValue::Decimal(val) => query.bind(val),
+        MySQLValue::String(val) => query.bind(val),
+        MySQLValue::Bytes(val) => query.bind(val),
+        MySQLValue::Date(val) => query.bind(val),
+        MySQLValue::Time(val) => query.bind(val),
+        MySQLValue::DateTime(val) => query.bind(val),
+        MySQLValue::Timestamp(val) => query.bind(val)
+    })
+}
+
+pub(crate) async fn fetch_rows<'e, E>(executor: E, sql: &str, params: &[MySQLValue]) -> Result<Vec<MySqlRow>, MySQLAccessorError>
+    where E: sqlx::Executor<'e, Database = MySql> {
+    let map_fetch_row_err: fn(sqlx::Error) -> MySQLAccessorError = move |e| MySQLAccessorError { err_type: MySQLAccessorErrorType::SqlFetchRowError(e) };
+    let mut rows = build_query(sql, params)
+        .fetch(executor);
+    let mut rst: Vec<MySqlRow> = Vec::new();
+    while let Some(row) = rows.try_next().await.map_err(map_fetch_row_err)? {
+        rst.push(row);
+    }
+    Ok(rst)
+}
+
+pub(crate) async fn fetch_optional_row<'e, E>(executor: E, sql: &str, params: &[MySQLValue]) -> Result<Option<MySqlRow>, MySQLAccessorError>
+    where E: sqlx::Executor<'e, Database = MySql> {
+    build_query(sql, params)
+        .fetch_optional(executor)
+        .await
+        .map_err(|e| MySQLAccessorError { err_type: MySQLAccessorErrorType::SqlFetchRowError(e) })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MySQLExecuteResult {
+    pub(crate) rows_affected: u64,
+    pub(crate) last_insert_id: u64
+}
+
+impl MySQLExecuteResult {
+    pub fn rows_affected(&self) -> u64 {
+        self.rows_affected
+    }
+    pub fn last_insert_id(&self) -> u64 {
+        self.last_insert_id
+    }
+}
+
+pub(crate) async fn execute_sql<'e, E>(executor: E, sql: &str, params: &[MySQLValue]) -> Result<MySQLExecuteResult, MySQLAccessorError>
+    where E: sqlx::Executor<'e, Database = MySql> {
+    let done = build_query(sql, params)
+        .execute(executor)
+        .await
+        .map_err(|e| MySQLAccessorError { err_type: MySQLAccessorErrorType::SqlExecuteError(e) })?;
+    Ok(MySQLExecuteResult {
+        rows_affected: done.rows_affected(),
+        last_insert_id: done.last_insert_id()
+    })
+}
+
+pub(crate) fn stream_rows<'e, 'c: 'e, E>(executor: E, sql: &'e str, params: &[MySQLValue]) -> BoxStream<'e, Result<MySqlRow, MySQLAccessorError>>
+    where E: sqlx::Executor<'c, Database = MySql> + 'e {
+    build_query(sql, params)
+        .fetch(executor)
+        .map_err(|e| MySQLAccessorError { err_type: MySQLAccessorErrorType::SqlFetchRowError(e) })
+        .boxed()
+}
+
+pub(crate) fn stream_rows_as<'e, 'c: 'e, E, T>(executor: E, sql: &'e str, params: &[MySQLValue]) -> BoxStream<'e, Result<T, MySQLAccessorError>>
+    where E: sqlx::Executor<'c, Database = MySql> + 'e,
+          T: for<'r> sqlx::FromRow<'r, MySqlRow> + Send + 'e {
+    decode_stream(stream_rows(executor, sql, params))
+}
+
+pub(crate) fn decode_stream<'e, T>(rows: BoxStream<'e, Result<MySqlRow, MySQLAccessorError>>) -> BoxStream<'e, Result<T, MySQLAccessorError>>
+    where T: for<'r> sqlx::FromRow<'r, MySqlRow> + Send + 'e {
+    rows.map(|row| row.and_then(|row| T::from_row(&row).map_err(|e| map_decode_err(&row, e))))
+        .boxed()
+}
+
+fn map_decode_err(row: &MySqlRow, e: sqlx::Error) -> MySQLAccessorError {
+    let column = match &e {
+        sqlx::Error::ColumnDecode { index, .. } => match index.parse::<usize>() {
+            Ok(pos) => row.columns().get(pos).map_or(index.clone(), |column| column.name().to_string()),
+            Err(_) => index.trim_matches('"').to_string()
+        },
+        sqlx::Error::ColumnNotFound(name) => name.clone(),
+        sqlx::Error::ColumnIndexOutOfBounds { index, .. } => index.to_string(),
+        _ => String::new()
+    };
+    MySQLAccessorError { err_type: MySQLAccessorErrorType::RowDecodeError(column, e) }
+}
+
+pub(crate) fn decode_rows<T>(rows: Vec<MySqlRow>) -> Result<Vec<T>, MySQLAccessorError>
+    where T: for<'r> sqlx::FromRow<'r, MySqlRow> {
+    rows.iter()
+        .map(|row| T::from_row(row).map_err(|e| map_decode_err(row, e)))
+        .collect()
+}
+
+pub(crate) fn decode_optional_row<T>(row: Option<MySqlRow>) -> Result<Option<T>, MySQLAccessorError>
+    where T: for<'r> sqlx::FromRow<'r, MySqlRow> {
+    match row.as_ref() {
+        Some(row) => T::from_row(row).map(Some).map_err(|e| map_decode_err(row, e)),
+        None => Ok(None)
+    }
+}
+
+pub(crate) fn decode_scalar<T>(row: Option<MySqlRow>) -> Result<T, MySQLAccessorError>
+    where T: for<'r> sqlx::Decode<'r, MySql> + sqlx::Type<MySql> {
+    let row = row.ok_or(MySQLAccessorError { err_type: MySQLAccessorErrorType::SqlRowNotFound })?;
+    row.try_get::<T, usize>(0).map_err(|e| map_decode_err(&row, e))
 }
 
 pub trait MySQLAccessor {
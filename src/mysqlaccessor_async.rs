@@ -1,7 +1,12 @@
-use sqlx::mysql::{MySqlRow, MySqlConnectOptions};
+use futures::stream::BoxStream;
+use sqlx::mysql::{MySql, MySqlRow};
 use sqlx::Connection;
-use futures::TryStreamExt;
-use crate::mysqlaccessor::{MySQLAccessor, MySQLAccessorError, MySQLAccessorErrorType};
+use futures::future::BoxFuture;
+use std::sync::Arc;
+use tokio::time::{Duration, Instant};
+use crate::mysqlaccessor_batch::MySQLBatchInsert;
+use crate::mysqlaccessor_transaction::{MySQLIsolationLevel, MySQLTransaction, MySQLTransactionConn};
+use crate::mysqlaccessor::{MySQLAccessor, MySQLAccessorError, MySQLAccessorErrorType, MySQLValue, MySQLExecuteResult, MySQLSslMode, MySQLConnectConfig, delegate_connect_config, connect_with_retry, is_idempotent_sql, transaction_state_change, fetch_rows, fetch_optional_row, stream_rows, stream_rows_as, execute_sql, decode_rows, decode_optional_row, decode_scalar};
 
 macro_rules! check_conn_open {
     ($ins:expr) => {
@@ -15,103 +20,306 @@ macro_rules! check_conn_open {
     };
 }
 
-pub struct MySQLAccessorAsync<'a> {
-    pub(crate) host: &'a str,
-    pub(crate) port: u16,
-    pub(crate) user: &'a str,
-    pub(crate) passwd: &'a str,
-    pub(crate) db: &'a str,
-    pub(crate) charset: &'a str,
+macro_rules! fetch_reconnecting {
+    ($ins:expr, $fetch:ident, $sql:expr, $params:expr) => {
+        {
+            $ins.ensure_conn(false).await?;
+            match $fetch($ins.conn.as_mut().unwrap(), $sql, $params).await {
+                Err(e) if $ins.auto_reconnect && e.is_connection_error() => {
+                    let in_transaction = $ins.in_transaction;
+                    $ins.reconnect(&e).await?;
+                    if in_transaction || !is_idempotent_sql($sql) {
+                        return Err(e);
+                    }
+                    log::info!("retry idempotent query after reconnect");
+                    $fetch($ins.conn.as_mut().unwrap(), $sql, $params).await
+                },
+                rst => {
+                    $ins.track_transaction($sql, rst.is_ok());
+                    rst
+                }
+            }
+        }
+    };
+}
+
+#[derive(Debug)]
+pub enum MySQLReconnectEvent<'e> {
+    Lost(&'e MySQLAccessorError),
+    Reconnected,
+    ReconnectFailed(&'e MySQLAccessorError)
+}
+
+pub type MySQLReconnectCallback = Arc<dyn Fn(MySQLReconnectEvent<'_>) + Send + Sync>;
+
+pub struct MySQLAccessorAsync {
+    pub(crate) connect_config: MySQLConnectConfig,
+    pub(crate) isolation_level: Option<MySQLIsolationLevel>,
+    pub(crate) deadlock_retries: u32,
+    pub(crate) connect_retries: u32,
+    pub(crate) connect_retry_interval: u64,
+    pub(crate) auto_reconnect: bool,
+    pub(crate) ping_interval: Option<u64>,
+    pub(crate) on_reconnect: Option<MySQLReconnectCallback>,
 
     is_open_connection: bool,
+    in_transaction: bool,
+    last_used: Option<Instant>,
     pub(crate) conn: Option<sqlx::MySqlConnection>
 }
 
-impl<'a> MySQLAccessor for MySQLAccessorAsync<'a> {
+impl MySQLAccessor for MySQLAccessorAsync {
 }
 
-impl<'a> MySQLAccessorAsync<'a> {
+impl Default for MySQLAccessorAsync {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MySQLAccessorAsync {
     pub fn new() -> Self {
         Self {
-            host: "localhost",
-            port: 3308,
-            user: "root",
-            passwd: "",
-            db: "",
-            charset: "utf8",
+            connect_config: MySQLConnectConfig::new(),
+            isolation_level: None,
+            deadlock_retries: 3,
+            connect_retries: 0,
+            connect_retry_interval: 100,
+            auto_reconnect: true,
+            ping_interval: None,
+            on_reconnect: None,
             is_open_connection: false,
+            in_transaction: false,
+            last_used: None,
             conn: None,
         }
     }
 
-    pub fn host(mut self, host: &'a str) -> Self {
-        self.host = host;
+    pub fn connect_config(mut self, connect_config: MySQLConnectConfig) -> Self {
+        self.connect_config = connect_config;
         self
     }
 
-    pub fn port(mut self, port: u16) -> Self {
-        self.port = port;
+    delegate_connect_config!(
+        host: &str,
+        port: u16,
+        user: &str,
+        passwd: &str,
+        db: &str,
+        charset: &str,
+        url: &str,
+        socket: &str,
+        ssl_mode: MySQLSslMode,
+        ssl_ca: &str,
+        collation: &str,
+        time_zone: &str,
+        statement_cache_capacity: usize
+    );
+
+    pub fn isolation_level(mut self, isolation_level: MySQLIsolationLevel) -> Self {
+        self.isolation_level = Some(isolation_level);
         self
     }
 
-    pub fn user(mut self, user: &'a str) -> Self {
-        self.user = user;
+    pub fn deadlock_retries(mut self, deadlock_retries: u32) -> Self {
+        self.deadlock_retries = deadlock_retries;
         self
     }
 
-    pub fn passwd(mut self, passwd: &'a str) -> Self {
-        self.passwd = passwd;
+    pub fn connect_retries(mut self, connect_retries: u32) -> Self {
+        self.connect_retries = connect_retries;
         self
     }
 
-    pub fn db(mut self, db: &'a str) -> Self {
-        self.db = db;
+    pub fn connect_retry_interval(mut self, connect_retry_interval: u64) -> Self {
+        self.connect_retry_interval = connect_retry_interval;
         self
     }
 
-    pub fn charset(mut self, charset: &'a str) -> Self {
-        self.charset = charset;
+    pub fn auto_reconnect(mut self, auto_reconnect: bool) -> Self {
+        self.auto_reconnect = auto_reconnect;
         self
     }
 
-    fn get_connect_option(&self) -> MySqlConnectOptions {
-        sqlx::mysql::MySqlConnectOptions::new()
-            .username(self.user)
-            .password(self.passwd)
-            .host(self.host)
-            .port(self.port)
-            .database(self.db)
-            .charset(self.charset)
+    pub fn ping_interval(mut self, ping_interval: u64) -> Self {
+        self.ping_interval = Some(ping_interval);
+        self
+    }
+
+    pub fn on_reconnect<F>(mut self, on_reconnect: F) -> Self
+        where F: Fn(MySQLReconnectEvent<'_>) + Send + Sync + 'static {
+        self.on_reconnect = Some(Arc::new(on_reconnect));
+        self
     }
 
     pub async fn open_connection(&mut self) -> Result<(), MySQLAccessorError> {
-        self.is_open_connection = true;
         if self.conn.is_none() {
-            let connect_options = self.get_connect_option();
-            println!("{:?}", connect_options);
-            self.conn = match sqlx::MySqlConnection::connect_with(&connect_options).await {
-                Ok(conn) => Some(conn),
-                Err(e) => {
-                    println!("{:?}", e);
-                    None
-                }
-            };
+            let connect_options = self.connect_config.get_connect_option()?;
+            let dsn = self.connect_config.get_dsn();
+            let conn = connect_with_retry(dsn.as_str(), self.connect_retries, self.connect_retry_interval, || {
+                self.connect_config.connect(&connect_options)
+            }).await?;
+            self.conn = Some(conn);
         }
+        self.is_open_connection = true;
         Ok(())
     }
 
-    pub async fn do_sql(&mut self, sql: &str) -> Result<Option<Vec<sqlx::mysql::MySqlRow>>, MySQLAccessorError> {
-        if self.conn.is_none() && !self.is_open_connection {
+    pub async fn ping(&mut self) -> Result<(), MySQLAccessorError> {
+        check_conn_open!(self);
+        let e = match self.conn.as_mut().unwrap().ping().await {
+            Ok(()) => return Ok(()),
+            Err(e) => MySQLAccessorError { err_type: MySQLAccessorErrorType::PingError(e) }
+        };
+        match self.auto_reconnect {
+            true => self.reconnect(&e).await,
+            false => Err(e)
+        }
+    }
+
+    async fn reconnect(&mut self, cause: &MySQLAccessorError) -> Result<(), MySQLAccessorError> {
+        log::warn!("mysql connection to {} lost: {:?}", self.connect_config.get_dsn(), cause);
+        if let Some(on_reconnect) = self.on_reconnect.as_ref() {
+            on_reconnect(MySQLReconnectEvent::Lost(cause));
+        }
+        self.conn = None;
+        self.in_transaction = false;
+        match self.open_connection().await {
+            Ok(()) => {
+                if let Some(on_reconnect) = self.on_reconnect.as_ref() {
+                    on_reconnect(MySQLReconnectEvent::Reconnected);
+                }
+                Ok(())
+            },
+            Err(e) => {
+                if let Some(on_reconnect) = self.on_reconnect.as_ref() {
+                    on_reconnect(MySQLReconnectEvent::ReconnectFailed(&e));
+                }
+                Err(e)
+            }
+        }
+    }
+
+    async fn reconnect_on_error<T>(&mut self, rst: Result<T, MySQLAccessorError>) -> Result<T, MySQLAccessorError> {
+        if let Err(e) = rst.as_ref() {
+            if self.auto_reconnect && e.is_connection_error() {
+                let _ = self.reconnect(e).await;
+            }
+        }
+        rst
+    }
+
+    async fn ensure_conn(&mut self, ping: bool) -> Result<(), MySQLAccessorError> {
+        if self.conn.is_none() && (!self.is_open_connection || self.auto_reconnect) {
             self.open_connection().await?;
         }
         check_conn_open!(self);
-        let map_fetch_row_err: fn(sqlx::Error) -> MySQLAccessorError = move |e| MySQLAccessorError { err_type: MySQLAccessorErrorType::SqlFetchRowError(e) };
-        let mut rows = sqlx::query(sql)
-            .fetch(self.conn.as_mut().unwrap());
-        let mut rst: Vec<MySqlRow> = Vec::new();
-        while let Some(row) = rows.try_next().await.map_err(map_fetch_row_err)? {
-            rst.push(row);
+        let idle = match (self.last_used, self.ping_interval) {
+            (Some(last_used), Some(ping_interval)) => last_used.elapsed() >= Duration::from_secs(ping_interval),
+            _ => false
+        };
+        if self.auto_reconnect && (ping || idle) {
+            self.ping().await?;
+        }
+        self.last_used = Some(Instant::now());
+        Ok(())
+    }
+
+    pub async fn do_sql(&mut self, sql: &str) -> Result<Option<Vec<sqlx::mysql::MySqlRow>>, MySQLAccessorError> {
+        self.do_sql_params(sql, &[]).await
+    }
+
+    pub async fn do_sql_params(&mut self, sql: &str, params: &[MySQLValue]) -> Result<Option<Vec<MySqlRow>>, MySQLAccessorError> {
+        fetch_reconnecting!(self, fetch_rows, sql, params).map(Some)
+    }
+
+    async fn fetch_optional_row(&mut self, sql: &str, params: &[MySQLValue]) -> Result<Option<MySqlRow>, MySQLAccessorError> {
+        fetch_reconnecting!(self, fetch_optional_row, sql, params)
+    }
+
+    fn track_transaction(&mut self, sql: &str, succeeded: bool) {
+        if succeeded {
+            if let Some(in_transaction) = transaction_state_change(sql) {
+                self.in_transaction = in_transaction;
+            }
+        }
+    }
+
+    pub async fn execute(&mut self, sql: &str, params: &[MySQLValue]) -> Result<MySQLExecuteResult, MySQLAccessorError> {
+        self.ensure_conn(false).await?;
+        let rst = execute_sql(self.conn.as_mut().unwrap(), sql, params).await;
+        self.track_transaction(sql, rst.is_ok());
+        self.reconnect_on_error(rst).await
+    }
+
+    pub async fn batch_insert<I>(&mut self, batch: &MySQLBatchInsert, rows: I) -> Result<u64, MySQLAccessorError>
+        where I: IntoIterator<Item = Vec<MySQLValue>> {
+        self.ensure_conn(false).await?;
+        let rst = batch.execute(self.conn.as_mut().unwrap(), rows).await;
+        self.reconnect_on_error(rst).await
+    }
+
+    pub async fn begin(&mut self) -> Result<MySQLTransaction<'_>, MySQLAccessorError> {
+        self.ensure_conn(true).await?;
+        let isolation_level = self.isolation_level;
+        MySQLTransaction::begin(MySQLTransactionConn::Connection(self.conn.as_mut().unwrap()), isolation_level).await
+    }
+
+    pub async fn transaction<F, T>(&mut self, mut f: F) -> Result<T, MySQLAccessorError>
+        where F: for<'t, 'c> FnMut(&'t mut MySQLTransaction<'c>) -> BoxFuture<'t, Result<T, MySQLAccessorError>> {
+        let mut attempt = 0;
+        loop {
+            let mut tx = self.begin().await?;
+            let rst = match f(&mut tx).await {
+                Ok(val) => tx.commit().await.map(|_| val),
+                Err(e) => {
+                    let _ = tx.rollback().await;
+                    Err(e)
+                }
+            };
+            match rst {
+                Err(e) if e.is_deadlock() && attempt < self.deadlock_retries => {
+                    attempt += 1;
+                    log::warn!("transaction deadlock, retry {}/{}", attempt, self.deadlock_retries);
+                    tokio::time::delay_for(Duration::from_millis(50 * attempt as u64)).await;
+                },
+                rst => return rst
+            }
         }
-        Ok(Some(rst))
+    }
+
+    pub async fn fetch_stream<'s>(&'s mut self, sql: &'s str, params: &[MySQLValue]) -> Result<BoxStream<'s, Result<MySqlRow, MySQLAccessorError>>, MySQLAccessorError> {
+        self.ensure_conn(false).await?;
+        Ok(stream_rows(self.conn.as_mut().unwrap(), sql, params))
+    }
+
+    pub async fn fetch_stream_as<'s, T>(&'s mut self, sql: &'s str, params: &[MySQLValue]) -> Result<BoxStream<'s, Result<T, MySQLAccessorError>>, MySQLAccessorError>
+        where T: for<'r> sqlx::FromRow<'r, MySqlRow> + Send + 's {
+        self.ensure_conn(false).await?;
+        Ok(stream_rows_as(self.conn.as_mut().unwrap(), sql, params))
+    }
+
+    pub async fn fetch_all_as<T>(&mut self, sql: &str, params: &[MySQLValue]) -> Result<Vec<T>, MySQLAccessorError>
+        where T: for<'r> sqlx::FromRow<'r, MySqlRow> {
+        let rows = self.do_sql_params(sql, params).await?.unwrap_or_default();
+        decode_rows(rows)
+    }
+
+    pub async fn fetch_one_as<T>(&mut self, sql: &str, params: &[MySQLValue]) -> Result<T, MySQLAccessorError>
+        where T: for<'r> sqlx::FromRow<'r, MySqlRow> {
+        self.fetch_optional_as(sql, params).await?
+            .ok_or(MySQLAccessorError { err_type: MySQLAccessorErrorType::SqlRowNotFound })
+    }
+
+    pub async fn fetch_optional_as<T>(&mut self, sql: &str, params: &[MySQLValue]) -> Result<Option<T>, MySQLAccessorError>
+        where T: for<'r> sqlx::FromRow<'r, MySqlRow> {
+        let row = self.fetch_optional_row(sql, params).await?;
+        decode_optional_row(row)
+    }
+
+    pub async fn fetch_scalar<T>(&mut self, sql: &str, params: &[MySQLValue]) -> Result<T, MySQLAccessorError>
+        where T: for<'r> sqlx::Decode<'r, MySql> + sqlx::Type<MySql> {
+        let row = self.fetch_optional_row(sql, params).await?;
+        decode_scalar(row)
     }
 }
@@ -0,0 +1,183 @@
+use sqlx::mysql::MySqlConnection;
+use crate::mysqlaccessor::{MySQLAccessorError, MySQLAccessorErrorType, MySQLValue, execute_sql, fetch_optional_row, decode_scalar};
+
+const MYSQL_MAX_PLACEHOLDERS: usize = 65535;
+const MYSQL_PACKET_HEADROOM: usize = 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MySQLInsertMode {
+    Insert,
+    InsertIgnore,
+    Replace,
+    OnDuplicateKeyUpdate(Vec<String>)
+}
+
+#[derive(Debug, Clone)]
+pub struct MySQLBatchInsert {
+    pub(crate) table: String,
+    pub(crate) columns: Vec<String>,
+    pub(crate) mode: MySQLInsertMode,
+    pub(crate) max_packet_size: Option<usize>,
+    pub(crate) max_rows: usize
+}
+
+fn quote_identifier(identifier: &str) -> String {
+    identifier.split('.')
+        .map(|part| format!("`{}`", part.trim_matches('`').replace('`', "``")))
+        .collect::<Vec<String>>()
+        .join(".")
+}
+
+// MySQL 8.0.19 added row aliases and 8.0.20 deprecated VALUES() in ON DUPLICATE KEY UPDATE, MariaDB only has VALUES()
+fn supports_row_alias(version: &str) -> bool {
+    if version.to_lowercase().contains("mariadb") {
+        return false;
+    }
+    let parts: Vec<u32> = version.split(|c: char| !c.is_ascii_digit())
+        .take(3)
+        .map(|part| part.parse::<u32>().unwrap_or(0))
+        .collect();
+    match parts.as_slice() {
+        [major, minor, patch] => (*major, *minor, *patch) >= (8, 0, 19),
+        _ => false
+    }
+}
+
+fn estimate_value_size(val: &MySQLValue) -> usize {
+    let payload = match val {
+        MySQLValue::Null => 0,
+        MySQLValue::Bool(_) => 1,
+        MySQLValue::Int(_) | MySQLValue::UInt(_) | MySQLValue::Float(_) => 8,
+        MySQLValue::Decimal(val) => val.to_string().len() + 9,
+        MySQLValue::String(val) => val.len() + 9,
+        MySQLValue::Bytes(val) => val.len() + 9,
+        MySQLValue::Date(_) => 5,
+        MySQLValue::Time(_) => 13,
+        MySQLValue::DateTime(_) | MySQLValue::Timestamp(_) => 12
+    };
+    payload + 2
+}
+
+impl MySQLBatchInsert {
+    pub fn new(table: &str, columns: &[&str]) -> Self {
+        Self {
+            table: table.to_string(),
+            columns: columns.iter().map(|column| column.to_string()).collect(),
+            mode: MySQLInsertMode::Insert,
+            max_packet_size: None,
+            max_rows: 1000
+        }
+    }
+
+    pub fn mode(mut self, mode: MySQLInsertMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn max_packet_size(mut self, max_packet_size: usize) -> Self {
+        self.max_packet_size = Some(max_packet_size);
+        self
+    }
+
+    pub fn max_rows(mut self, max_rows: usize) -> Self {
+        self.max_rows = std::cmp::max(max_rows, 1);
+        self
+    }
+
+    fn build_sql(&self, row_count: usize, row_alias: bool) -> String {
+        let verb = match self.mode {
+            MySQLInsertMode::Insert | MySQLInsertMode::OnDuplicateKeyUpdate(_) => "INSERT INTO",
+            MySQLInsertMode::InsertIgnore => "INSERT IGNORE INTO",
+            MySQLInsertMode::Replace => "REPLACE INTO"
+        };
+        let columns: Vec<String> = self.columns.iter().map(|column| quote_identifier(column)).collect();
+        let placeholders = format!("({})", vec!["?"; self.columns.len()].join(", "));
+        let mut sql = format!(
+            "{} {} ({}) VALUES {}",
+            verb,
+            quote_identifier(self.table.as_str()),
+            columns.join(", "),
+            vec![placeholders.as_str(); row_count].join(", ")
+        );
+        if let MySQLInsertMode::OnDuplicateKeyUpdate(update_columns) = &self.mode {
+            let update_columns = match update_columns.is_empty() {
+                true => &self.columns,
+                false => update_columns
+            };
+            let updates: Vec<String> = update_columns.iter()
+                .map(|column| match row_alias {
+                    true => format!("{0} = `new`.{0}", quote_identifier(column)),
+                    false => format!("{0} = VALUES({0})", quote_identifier(column))
+                })
+                .collect();
+            if row_alias {
+                sql = format!("{} AS `new`", sql);
+            }
+            sql = format!("{} ON DUPLICATE KEY UPDATE {}", sql, updates.join(", "));
+        }
+        sql
+    }
+
+    fn row_sql_size(&self) -> usize {
+        self.columns.len() * 3 + 2
+    }
+
+    async fn packet_limit(&self, conn: &mut MySqlConnection) -> Result<usize, MySQLAccessorError> {
+        if let Some(max_packet_size) = self.max_packet_size {
+            return Ok(max_packet_size);
+        }
+        let row = fetch_optional_row(conn, "SELECT CAST(@@max_allowed_packet AS SIGNED)", &[]).await?;
+        let max_allowed_packet: i64 = decode_scalar(row)?;
+        Ok(max_allowed_packet as usize)
+    }
+
+    async fn row_alias(&self, conn: &mut MySqlConnection) -> Result<bool, MySQLAccessorError> {
+        if !matches!(self.mode, MySQLInsertMode::OnDuplicateKeyUpdate(_)) {
+            return Ok(false);
+        }
+        let row = fetch_optional_row(conn, "SELECT VERSION()", &[]).await?;
+        let version: String = decode_scalar(row)?;
+        Ok(supports_row_alias(version.as_str()))
+    }
+
+    async fn flush(&self, conn: &mut MySqlConnection, row_count: usize, row_alias: bool, params: &mut Vec<MySQLValue>) -> Result<u64, MySQLAccessorError> {
+        let sql = self.build_sql(row_count, row_alias);
+        let rst = execute_sql(conn, sql.as_str(), params.as_slice()).await?;
+        params.clear();
+        Ok(rst.rows_affected())
+    }
+
+    // every chunk is its own statement: outside a transaction, an error leaves the chunks already sent committed
+    pub(crate) async fn execute<I>(&self, conn: &mut MySqlConnection, rows: I) -> Result<u64, MySQLAccessorError>
+        where I: IntoIterator<Item = Vec<MySQLValue>> {
+        if self.columns.is_empty() {
+            return Err(MySQLAccessorError { err_type: MySQLAccessorErrorType::InvalidBatchColumns });
+        }
+        let packet_limit = self.packet_limit(conn).await?.saturating_sub(MYSQL_PACKET_HEADROOM);
+        let max_rows = std::cmp::min(self.max_rows, MYSQL_MAX_PLACEHOLDERS / self.columns.len());
+        let row_alias = self.row_alias(conn).await?;
+        let base_size = self.build_sql(0, row_alias).len();
+        let mut rows_affected = 0;
+        let mut params: Vec<MySQLValue> = Vec::new();
+        let mut row_count = 0;
+        let mut chunk_size = base_size;
+        for (index, row) in rows.into_iter().enumerate() {
+            if row.len() != self.columns.len() {
+                return Err(MySQLAccessorError { err_type: MySQLAccessorErrorType::InvalidBatchRow(index) });
+            }
+            let row_size = self.row_sql_size() + row.iter().map(estimate_value_size).sum::<usize>();
+            if row_count > 0 && (row_count >= max_rows || chunk_size + row_size > packet_limit) {
+                rows_affected += self.flush(conn, row_count, row_alias, &mut params).await?;
+                row_count = 0;
+                chunk_size = base_size;
+            }
+            params.extend(row);
+            row_count += 1;
+            chunk_size += row_size;
+        }
+        if row_count > 0 {
+            rows_affected += self.flush(conn, row_count, row_alias, &mut params).await?;
+        }
+        Ok(rows_affected)
+    }
+}
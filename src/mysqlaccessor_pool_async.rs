@@ -1,10 +1,16 @@
-use sqlx::mysql::{MySqlRow, MySqlConnectOptions, MySqlPoolOptions};
+use futures::StreamExt;
+use futures::stream::BoxStream;
+use sqlx::mysql::{MySql, MySqlRow, MySqlPoolOptions};
 use sqlx::Connection;
-use futures::TryStreamExt;
-use crate::mysqlaccessor_async::MySQLAccessorAsync;
-use crate::mysqlaccessor::{MySQLAccessor, MySQLAccessorError, MySQLAccessorErrorType};
+use sqlx::pool::PoolConnection;
+use futures::future::BoxFuture;
+use crate::mysqlaccessor_batch::MySQLBatchInsert;
+use crate::mysqlaccessor_transaction::{MySQLIsolationLevel, MySQLTransaction, MySQLTransactionConn};
+use crate::mysqlaccessor::{MySQLAccessor, MySQLAccessorError, MySQLAccessorErrorType, MySQLValue, MySQLExecuteResult, MySQLSslMode, MySQLConnectConfig, delegate_connect_config, init_session, connect_with_retry, fetch_rows, fetch_optional_row, stream_rows, decode_stream, execute_sql, decode_rows, decode_optional_row, decode_scalar};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::sync::mpsc;
+use tokio::time::Duration;
 
 macro_rules! check_conn_pool_open {
     ($ins:expr) => {
@@ -20,99 +26,330 @@ macro_rules! check_conn_pool_open {
     };
 }
 
+#[derive(Debug, Clone)]
+pub struct MySQLAccessorPoolStats {
+    pub size: u32,
+    pub idle: usize,
+    pub waiters: usize,
+    pub max_connections: u32,
+    pub is_closed: bool
+}
+
+struct PoolWaiterGuard<'a> {
+    waiters: &'a AtomicUsize
+}
+
+impl<'a> PoolWaiterGuard<'a> {
+    fn new(waiters: &'a AtomicUsize) -> Self {
+        waiters.fetch_add(1, Ordering::SeqCst);
+        Self {
+            waiters
+        }
+    }
+}
+
+impl<'a> Drop for PoolWaiterGuard<'a> {
+    fn drop(&mut self) {
+        self.waiters.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+async fn acquire(conn_pool: &sqlx::MySqlPool, waiters: &AtomicUsize) -> Result<PoolConnection<MySql>, MySQLAccessorError> {
+    let _waiter = PoolWaiterGuard::new(waiters);
+    conn_pool.acquire().await
+        .map_err(|e| MySQLAccessorError { err_type: MySQLAccessorErrorType::AcquireConnError(e) })
+}
+
 #[derive(Clone)]
-pub struct MySQLAccessorPoolAsync<'a> {
-    pub(crate) host: &'a str,
-    pub(crate) port: u16,
-    pub(crate) user: &'a str,
-    pub(crate) passwd: &'a str,
-    pub(crate) db: &'a str,
-    pub(crate) charset: &'a str,
-
-    pub(crate) conn_pool: Option<sqlx::MySqlPool>
+pub struct MySQLAccessorPoolAsync {
+    pub(crate) connect_config: MySQLConnectConfig,
+    pub(crate) min_connections: u32,
+    pub(crate) max_connections: u32,
+    pub(crate) connect_timeout: u64,
+    pub(crate) acquire_timeout: u64,
+    pub(crate) idle_timeout: Option<u64>,
+    pub(crate) max_lifetime: Option<u64>,
+    pub(crate) test_before_acquire: bool,
+    pub(crate) isolation_level: Option<MySQLIsolationLevel>,
+    pub(crate) deadlock_retries: u32,
+    pub(crate) connect_retries: u32,
+    pub(crate) connect_retry_interval: u64,
+
+    pub(crate) conn_pool: Option<sqlx::MySqlPool>,
+    waiters: Arc<AtomicUsize>
 }
 
-impl<'a> MySQLAccessor for MySQLAccessorPoolAsync<'a> {
+impl MySQLAccessor for MySQLAccessorPoolAsync {
 }
 
-impl<'a> MySQLAccessorPoolAsync<'a> {
+impl Default for MySQLAccessorPoolAsync {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MySQLAccessorPoolAsync {
     pub fn new() -> Self {
         Self {
-            host: "localhost",
-            port: 3308,
-            user: "root",
-            passwd: "",
-            db: "",
-            charset: "utf8",
+            connect_config: MySQLConnectConfig::new(),
+            min_connections: 0,
+            max_connections: 10,
+            connect_timeout: 30,
+            acquire_timeout: 30,
+            idle_timeout: Some(600),
+            max_lifetime: Some(1800),
+            test_before_acquire: true,
+            isolation_level: None,
+            deadlock_retries: 3,
+            connect_retries: 0,
+            connect_retry_interval: 100,
             conn_pool: None,
+            waiters: Arc::new(AtomicUsize::new(0))
         }
     }
 
-    pub fn host(mut self, host: &'a str) -> Self {
-        self.host = host;
+    pub fn connect_config(mut self, connect_config: MySQLConnectConfig) -> Self {
+        self.connect_config = connect_config;
+        self
+    }
+
+    delegate_connect_config!(
+        host: &str,
+        port: u16,
+        user: &str,
+        passwd: &str,
+        db: &str,
+        charset: &str,
+        url: &str,
+        socket: &str,
+        ssl_mode: MySQLSslMode,
+        ssl_ca: &str,
+        collation: &str,
+        time_zone: &str,
+        statement_cache_capacity: usize
+    );
+
+    pub fn min_connections(mut self, min_connections: u32) -> Self {
+        self.min_connections = min_connections;
+        self
+    }
+
+    pub fn max_connections(mut self, max_connections: u32) -> Self {
+        self.max_connections = max_connections;
+        self
+    }
+
+    /// Bounds `open_connection` only, sqlx 0.4 has no per-connection connect timeout. Connections the
+    /// pool opens later are bounded by `acquire_timeout`, which sqlx applies to the whole `acquire()`.
+    pub fn connect_timeout(mut self, connect_timeout: u64) -> Self {
+        self.connect_timeout = connect_timeout;
+        self
+    }
+
+    pub fn acquire_timeout(mut self, acquire_timeout: u64) -> Self {
+        self.acquire_timeout = acquire_timeout;
+        self
+    }
+
+    pub fn idle_timeout(mut self, idle_timeout: Option<u64>) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    pub fn max_lifetime(mut self, max_lifetime: Option<u64>) -> Self {
+        self.max_lifetime = max_lifetime;
         self
     }
 
-    pub fn port(mut self, port: u16) -> Self {
-        self.port = port;
+    pub fn test_before_acquire(mut self, test_before_acquire: bool) -> Self {
+        self.test_before_acquire = test_before_acquire;
         self
     }
 
-    pub fn user(mut self, user: &'a str) -> Self {
-        self.user = user;
+    pub fn isolation_level(mut self, isolation_level: MySQLIsolationLevel) -> Self {
+        self.isolation_level = Some(isolation_level);
         self
     }
 
-    pub fn passwd(mut self, passwd: &'a str) -> Self {
-        self.passwd = passwd;
+    pub fn deadlock_retries(mut self, deadlock_retries: u32) -> Self {
+        self.deadlock_retries = deadlock_retries;
         self
     }
 
-    pub fn db(mut self, db: &'a str) -> Self {
-        self.db = db;
+    pub fn connect_retries(mut self, connect_retries: u32) -> Self {
+        self.connect_retries = connect_retries;
         self
     }
 
-    pub fn charset(mut self, charset: &'a str) -> Self {
-        self.charset = charset;
+    pub fn connect_retry_interval(mut self, connect_retry_interval: u64) -> Self {
+        self.connect_retry_interval = connect_retry_interval;
         self
     }
 
-    fn get_connect_option(&self) -> MySqlConnectOptions {
-        sqlx::mysql::MySqlConnectOptions::new()
-            .username(self.user)
-            .password(self.passwd)
-            .host(self.host)
-            .port(self.port)
-            .database(self.db)
-            .charset(self.charset)
+    fn get_pool_options(&self) -> MySqlPoolOptions {
+        let time_zone = self.connect_config.time_zone.clone();
+        MySqlPoolOptions::new()
+            .min_connections(self.min_connections)
+            .max_connections(std::cmp::max(self.max_connections, 1))
+            // sqlx 0.4's pool connect_timeout is the deadline for acquire(), including opening a connection
+            .connect_timeout(Duration::from_secs(self.acquire_timeout))
+            .idle_timeout(self.idle_timeout.map(Duration::from_secs))
+            .max_lifetime(self.max_lifetime.map(Duration::from_secs))
+            .test_before_acquire(self.test_before_acquire)
+            .after_connect(move |conn| {
+                let time_zone = time_zone.clone();
+                Box::pin(async move { init_session(conn, time_zone.as_deref()).await })
+            })
     }
 
     pub async fn open_connection(&mut self) -> Result<(), MySQLAccessorError> {
         if self.conn_pool.is_none() {
-            // let connect_uri = format!("mysql://{}:{}@{}:{}/{}", self.user, self.passwd, self.host, self.port, self.db);
-            let connect_options = self.get_connect_option();
-            self.conn_pool = match MySqlPoolOptions::new()
-                .max_connections(2)
-                .connect_with(connect_options)
-                .await
-            {
-                Ok(pool) => Some(pool.clone()),
-                Err(_e) => None
-            };
+            let connect_options = self.connect_config.get_connect_option()?;
+            let connect_timeout = Duration::from_secs(self.connect_timeout);
+            let dsn = self.connect_config.get_dsn();
+            let pool = connect_with_retry(dsn.as_str(), self.connect_retries, self.connect_retry_interval, || {
+                let pool_options = self.get_pool_options();
+                let connect_options = connect_options.clone();
+                let connect_config = &self.connect_config;
+                async move {
+                    // sqlx treats io errors during pool init as a server still booting and returns an empty
+                    // pool, so probe one connection first to surface an unreachable server here
+                    tokio::time::timeout(connect_timeout, async move {
+                        connect_config.connect(&connect_options).await?.close().await?;
+                        pool_options.connect_with(connect_options).await
+                    }).await.unwrap_or_else(|_| Err(sqlx::Error::Io(std::io::Error::new(std::io::ErrorKind::TimedOut, "connect timed out"))))
+                }
+            }).await?;
+            self.conn_pool = Some(pool);
         }
         Ok(())
     }
 
-    pub async fn do_sql(&self, sql: &str) -> Result<Option<Vec<sqlx::mysql::MySqlRow>>, MySQLAccessorError> {
+    pub fn pool_stats(&self) -> Result<MySQLAccessorPoolStats, MySQLAccessorError> {
         check_conn_pool_open!(self);
-        let map_fetch_row_err: fn(sqlx::Error) -> MySQLAccessorError = move |e| MySQLAccessorError { err_type: MySQLAccessorErrorType::SqlFetchRowError(e) };
-        let mut rows = sqlx::query(sql)
-            .fetch(self.conn_pool.as_ref().unwrap());
-        let mut rst: Vec<MySqlRow> = Vec::new();
-        while let Some(row) = rows.try_next().await.map_err(map_fetch_row_err)? {
-            rst.push(row);
-        }
+        let pool = self.conn_pool.as_ref().unwrap();
+        Ok(MySQLAccessorPoolStats {
+            size: pool.size(),
+            idle: pool.num_idle(),
+            waiters: self.waiters.load(Ordering::SeqCst),
+            max_connections: std::cmp::max(self.max_connections, 1),
+            is_closed: pool.is_closed()
+        })
+    }
+
+    pub(crate) async fn acquire_conn(&self) -> Result<PoolConnection<MySql>, MySQLAccessorError> {
+        check_conn_pool_open!(self);
+        acquire(self.conn_pool.as_ref().unwrap(), self.waiters.as_ref()).await
+    }
+
+    pub async fn do_sql(&self, sql: &str) -> Result<Option<Vec<sqlx::mysql::MySqlRow>>, MySQLAccessorError> {
+        self.do_sql_params(sql, &[]).await
+    }
+
+    pub async fn do_sql_params(&self, sql: &str, params: &[MySQLValue]) -> Result<Option<Vec<MySqlRow>>, MySQLAccessorError> {
+        let mut conn = self.acquire_conn().await?;
+        let rst = fetch_rows(&mut conn, sql, params).await?;
         Ok(Some(rst))
     }
+
+    pub(crate) async fn fetch_optional_row(&self, sql: &str, params: &[MySQLValue]) -> Result<Option<MySqlRow>, MySQLAccessorError> {
+        let mut conn = self.acquire_conn().await?;
+        fetch_optional_row(&mut conn, sql, params).await
+    }
+
+    pub async fn execute(&self, sql: &str, params: &[MySQLValue]) -> Result<MySQLExecuteResult, MySQLAccessorError> {
+        let mut conn = self.acquire_conn().await?;
+        execute_sql(&mut conn, sql, params).await
+    }
+
+    pub async fn batch_insert<I>(&self, batch: &MySQLBatchInsert, rows: I) -> Result<u64, MySQLAccessorError>
+        where I: IntoIterator<Item = Vec<MySQLValue>> {
+        let mut conn = self.acquire_conn().await?;
+        batch.execute(&mut conn, rows).await
+    }
+
+    pub async fn begin(&self) -> Result<MySQLTransaction<'static>, MySQLAccessorError> {
+        let conn = self.acquire_conn().await?;
+        MySQLTransaction::begin(MySQLTransactionConn::Pooled(Box::new(conn)), self.isolation_level).await
+    }
+
+    pub async fn transaction<F, T>(&self, mut f: F) -> Result<T, MySQLAccessorError>
+        where F: for<'t, 'c> FnMut(&'t mut MySQLTransaction<'c>) -> BoxFuture<'t, Result<T, MySQLAccessorError>> {
+        let mut attempt = 0;
+        loop {
+            let mut tx = self.begin().await?;
+            let rst = match f(&mut tx).await {
+                Ok(val) => tx.commit().await.map(|_| val),
+                Err(e) => {
+                    let _ = tx.rollback().await;
+                    Err(e)
+                }
+            };
+            match rst {
+                Err(e) if e.is_deadlock() && attempt < self.deadlock_retries => {
+                    attempt += 1;
+                    log::warn!("transaction deadlock, retry {}/{}", attempt, self.deadlock_retries);
+                    tokio::time::delay_for(Duration::from_millis(50 * attempt as u64)).await;
+                },
+                rst => return rst
+            }
+        }
+    }
+
+    pub fn fetch_stream<'s>(&'s self, sql: &'s str, params: &[MySQLValue]) -> BoxStream<'s, Result<MySqlRow, MySQLAccessorError>> {
+        let conn_pool = match self.conn_pool.clone() {
+            Some(conn_pool) => conn_pool,
+            None => return futures::stream::iter(vec![Err(MySQLAccessorError { err_type: MySQLAccessorErrorType::ConnNotOpen })]).boxed()
+        };
+        let waiters = self.waiters.clone();
+        let sql = sql.to_string();
+        let params = params.to_vec();
+        let (mut row_tx, row_rx) = mpsc::channel(1);
+        // the row stream borrows its connection, so read it on a task that owns the connection
+        tokio::spawn(async move {
+            let mut conn = match acquire(&conn_pool, waiters.as_ref()).await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    let _ = row_tx.send(Err(e)).await;
+                    return;
+                }
+            };
+            let mut rows = stream_rows(&mut conn, sql.as_str(), &params);
+            while let Some(row) = rows.next().await {
+                if row_tx.send(row).await.is_err() {
+                    break;
+                }
+            }
+        });
+        row_rx.boxed()
+    }
+
+    pub fn fetch_stream_as<'s, T>(&'s self, sql: &'s str, params: &[MySQLValue]) -> BoxStream<'s, Result<T, MySQLAccessorError>>
+        where T: for<'r> sqlx::FromRow<'r, MySqlRow> + Send + 's {
+        decode_stream(self.fetch_stream(sql, params))
+    }
+
+    pub async fn fetch_all_as<T>(&self, sql: &str, params: &[MySQLValue]) -> Result<Vec<T>, MySQLAccessorError>
+        where T: for<'r> sqlx::FromRow<'r, MySqlRow> {
+        let rows = self.do_sql_params(sql, params).await?.unwrap_or_default();
+        decode_rows(rows)
+    }
+
+    pub async fn fetch_one_as<T>(&self, sql: &str, params: &[MySQLValue]) -> Result<T, MySQLAccessorError>
+        where T: for<'r> sqlx::FromRow<'r, MySqlRow> {
+        self.fetch_optional_as(sql, params).await?
+            .ok_or(MySQLAccessorError { err_type: MySQLAccessorErrorType::SqlRowNotFound })
+    }
+
+    pub async fn fetch_optional_as<T>(&self, sql: &str, params: &[MySQLValue]) -> Result<Option<T>, MySQLAccessorError>
+        where T: for<'r> sqlx::FromRow<'r, MySqlRow> {
+        let row = self.fetch_optional_row(sql, params).await?;
+        decode_optional_row(row)
+    }
+
+    pub async fn fetch_scalar<T>(&self, sql: &str, params: &[MySQLValue]) -> Result<T, MySQLAccessorError>
+        where T: for<'r> sqlx::Decode<'r, MySql> + sqlx::Type<MySql> {
+        let row = self.fetch_optional_row(sql, params).await?;
+        decode_scalar(row)
+    }
 }
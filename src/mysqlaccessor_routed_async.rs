@@ -0,0 +1,350 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use futures::future::BoxFuture;
+use once_cell::sync::OnceCell;
+use futures::stream::BoxStream;
+use sqlx::mysql::{MySql, MySqlRow};
+use crate::mysqlaccessor_batch::MySQLBatchInsert;
+use crate::mysqlaccessor_pool_async::MySQLAccessorPoolAsync;
+use crate::mysqlaccessor_transaction::MySQLTransaction;
+use crate::mysqlaccessor::{MySQLAccessor, MySQLAccessorError, MySQLAccessorErrorType, MySQLValue, MySQLExecuteResult, is_idempotent_sql, decode_rows, decode_optional_row, decode_scalar};
+
+macro_rules! route_read {
+    ($ins:expr, $sql:expr, $method:ident($($arg:expr),*)) => {
+        {
+            if !$ins.is_replica_read($sql) {
+                let rst = $ins.primary.$method($($arg),*).await;
+                if !is_idempotent_sql($sql) {
+                    $ins.mark_write();
+                }
+                return rst;
+            }
+            for idx in $ins.replica_candidates() {
+                let rst = match $ins.replica_pool(idx).await {
+                    Ok(pool) => pool.$method($($arg),*).await,
+                    Err(e) => Err(e)
+                };
+                match rst {
+                    Err(e) if is_node_error(&e) => {
+                        log::warn!("mysql replica {} read fail, try next: {:?}", idx, e);
+                        $ins.report_failure(idx);
+                    },
+                    rst => {
+                        $ins.report_success(idx);
+                        return rst;
+                    }
+                }
+            }
+            $ins.primary.$method($($arg),*).await
+        }
+    };
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MySQLReplicaStrategy {
+    RoundRobin,
+    LeastConnections
+}
+
+struct MySQLReplica {
+    config: MySQLAccessorPoolAsync,
+    pool: OnceCell<MySQLAccessorPoolAsync>,
+    open_lock: tokio::sync::Mutex<()>,
+    consecutive_fails: AtomicU32,
+    ejected_until: Mutex<Option<Instant>>
+}
+
+#[derive(Clone)]
+pub struct MySQLAccessorRoutedAsync {
+    primary: MySQLAccessorPoolAsync,
+    replicas: Arc<Vec<MySQLReplica>>,
+    counter: Arc<AtomicUsize>,
+    last_write: Arc<Mutex<Option<Instant>>>,
+    pub(crate) strategy: MySQLReplicaStrategy,
+    pub(crate) sticky_window: u64,
+    pub(crate) max_fails: u32,
+    pub(crate) fail_timeout: u32
+}
+
+impl MySQLAccessor for MySQLAccessorRoutedAsync {
+}
+
+pub struct MySQLRoutedTransaction {
+    tx: MySQLTransaction<'static>,
+    last_write: Arc<Mutex<Option<Instant>>>
+}
+
+impl MySQLRoutedTransaction {
+    pub async fn commit(self) -> Result<(), MySQLAccessorError> {
+        self.tx.commit().await?;
+        mark_write(&self.last_write);
+        Ok(())
+    }
+
+    pub async fn rollback(self) -> Result<(), MySQLAccessorError> {
+        self.tx.rollback().await
+    }
+}
+
+impl Deref for MySQLRoutedTransaction {
+    type Target = MySQLTransaction<'static>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.tx
+    }
+}
+
+impl DerefMut for MySQLRoutedTransaction {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.tx
+    }
+}
+
+fn mark_write(last_write: &Mutex<Option<Instant>>) {
+    *last_write.lock().unwrap() = Some(Instant::now());
+}
+
+fn is_node_error(e: &MySQLAccessorError) -> bool {
+    match &e.err_type {
+        MySQLAccessorErrorType::ConnNotOpen
+        | MySQLAccessorErrorType::OpenConnError(_)
+        | MySQLAccessorErrorType::AcquireConnError(_) => true,
+        _ => e.is_connection_error()
+    }
+}
+
+impl MySQLAccessorRoutedAsync {
+    pub fn new(primary: MySQLAccessorPoolAsync, replicas: Vec<MySQLAccessorPoolAsync>) -> Self {
+        let replicas: Vec<MySQLReplica> = replicas.into_iter().map(|config| MySQLReplica {
+            config,
+            pool: OnceCell::new(),
+            open_lock: tokio::sync::Mutex::new(()),
+            consecutive_fails: AtomicU32::new(0),
+            ejected_until: Mutex::new(None)
+        }).collect();
+        Self {
+            primary,
+            replicas: Arc::new(replicas),
+            counter: Arc::new(AtomicUsize::new(0)),
+            last_write: Arc::new(Mutex::new(None)),
+            strategy: MySQLReplicaStrategy::RoundRobin,
+            sticky_window: 1000,
+            max_fails: 3,
+            fail_timeout: 30
+        }
+    }
+
+    pub fn strategy(mut self, strategy: MySQLReplicaStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    pub fn sticky_window(mut self, sticky_window: u64) -> Self {
+        self.sticky_window = sticky_window;
+        self
+    }
+
+    pub fn max_fails(mut self, max_fails: u32) -> Self {
+        self.max_fails = max_fails;
+        self
+    }
+
+    pub fn fail_timeout(mut self, fail_timeout: u32) -> Self {
+        self.fail_timeout = fail_timeout;
+        self
+    }
+
+    pub async fn open_connection(&mut self) -> Result<(), MySQLAccessorError> {
+        self.primary.open_connection().await?;
+        for idx in 0..self.replicas.len() {
+            if let Err(e) = self.replica_pool(idx).await {
+                log::warn!("open mysql replica {} fail, ejected: {:?}", idx, e);
+                self.eject(idx);
+            }
+        }
+        Ok(())
+    }
+
+    async fn replica_pool(&self, idx: usize) -> Result<&MySQLAccessorPoolAsync, MySQLAccessorError> {
+        let replica = &self.replicas[idx];
+        if let Some(pool) = replica.pool.get() {
+            return Ok(pool);
+        }
+        let _guard = replica.open_lock.lock().await;
+        if let Some(pool) = replica.pool.get() {
+            return Ok(pool);
+        }
+        let mut pool = replica.config.clone();
+        pool.open_connection().await?;
+        Ok(replica.pool.get_or_init(|| pool))
+    }
+
+    /// Clones share the read-your-writes window, a session starts with its own empty window.
+    pub fn session(&self) -> Self {
+        Self {
+            last_write: Arc::new(Mutex::new(None)),
+            ..self.clone()
+        }
+    }
+
+    pub fn primary(&self) -> &MySQLAccessorPoolAsync {
+        &self.primary
+    }
+
+    pub fn healthy_replica_count(&self) -> usize {
+        (0..self.replicas.len()).filter(|idx| !self.is_ejected(*idx)).count()
+    }
+
+    pub fn mark_write(&self) {
+        mark_write(&self.last_write);
+    }
+
+    fn in_sticky_window(&self) -> bool {
+        match *self.last_write.lock().unwrap() {
+            Some(last_write) => last_write.elapsed() < Duration::from_millis(self.sticky_window),
+            None => false
+        }
+    }
+
+    fn is_replica_read(&self, sql: &str) -> bool {
+        !self.replicas.is_empty() && is_idempotent_sql(sql) && !self.in_sticky_window()
+    }
+
+    fn is_ejected(&self, idx: usize) -> bool {
+        let mut ejected_until = self.replicas[idx].ejected_until.lock().unwrap();
+        match *ejected_until {
+            Some(until) if until > Instant::now() => true,
+            Some(_) => {
+                *ejected_until = None;
+                false
+            },
+            None => false
+        }
+    }
+
+    fn eject(&self, idx: usize) {
+        let state = &self.replicas[idx];
+        state.consecutive_fails.store(0, Ordering::SeqCst);
+        *state.ejected_until.lock().unwrap() = Some(Instant::now() + Duration::from_secs(self.fail_timeout as u64));
+    }
+
+    fn report_success(&self, idx: usize) {
+        self.replicas[idx].consecutive_fails.store(0, Ordering::SeqCst);
+    }
+
+    fn report_failure(&self, idx: usize) {
+        let fails = self.replicas[idx].consecutive_fails.fetch_add(1, Ordering::SeqCst) + 1;
+        if self.max_fails > 0 && fails >= self.max_fails {
+            log::warn!("mysql replica {} failed {} times, ejected for {}s", idx, fails, self.fail_timeout);
+            self.eject(idx);
+        }
+    }
+
+    fn replica_in_use(&self, idx: usize) -> usize {
+        self.replicas[idx].pool.get()
+            .and_then(|pool| pool.pool_stats().ok())
+            .map(|stats| (stats.size as usize).saturating_sub(stats.idle) + stats.waiters)
+            .unwrap_or(usize::MAX)
+    }
+
+    fn replica_candidates(&self) -> Vec<usize> {
+        let len = self.replicas.len();
+        if len == 0 {
+            return Vec::new();
+        }
+        let start = self.counter.fetch_add(1, Ordering::SeqCst) % len;
+        let mut candidates: Vec<usize> = (0..len).map(|offset| (start + offset) % len)
+            .filter(|idx| !self.is_ejected(*idx))
+            .collect();
+        if self.strategy == MySQLReplicaStrategy::LeastConnections {
+            candidates.sort_by_key(|idx| self.replica_in_use(*idx));
+        }
+        candidates
+    }
+
+    fn route_stream(&self, sql: &str) -> &MySQLAccessorPoolAsync {
+        if self.is_replica_read(sql) {
+            if let Some(pool) = self.replica_candidates().into_iter().find_map(|idx| self.replicas[idx].pool.get()) {
+                return pool;
+            }
+        }
+        &self.primary
+    }
+
+    pub async fn do_sql(&self, sql: &str) -> Result<Option<Vec<MySqlRow>>, MySQLAccessorError> {
+        self.do_sql_params(sql, &[]).await
+    }
+
+    pub async fn do_sql_params(&self, sql: &str, params: &[MySQLValue]) -> Result<Option<Vec<MySqlRow>>, MySQLAccessorError> {
+        route_read!(self, sql, do_sql_params(sql, params))
+    }
+
+    async fn fetch_optional_row(&self, sql: &str, params: &[MySQLValue]) -> Result<Option<MySqlRow>, MySQLAccessorError> {
+        route_read!(self, sql, fetch_optional_row(sql, params))
+    }
+
+    pub async fn execute(&self, sql: &str, params: &[MySQLValue]) -> Result<MySQLExecuteResult, MySQLAccessorError> {
+        let rst = self.primary.execute(sql, params).await;
+        self.mark_write();
+        rst
+    }
+
+    pub async fn batch_insert<I>(&self, batch: &MySQLBatchInsert, rows: I) -> Result<u64, MySQLAccessorError>
+        where I: IntoIterator<Item = Vec<MySQLValue>> {
+        let rst = self.primary.batch_insert(batch, rows).await;
+        self.mark_write();
+        rst
+    }
+
+    pub async fn begin(&self) -> Result<MySQLRoutedTransaction, MySQLAccessorError> {
+        let tx = self.primary.begin().await?;
+        Ok(MySQLRoutedTransaction {
+            tx,
+            last_write: self.last_write.clone()
+        })
+    }
+
+    pub async fn transaction<F, T>(&self, f: F) -> Result<T, MySQLAccessorError>
+        where F: for<'t, 'c> FnMut(&'t mut MySQLTransaction<'c>) -> BoxFuture<'t, Result<T, MySQLAccessorError>> {
+        let rst = self.primary.transaction(f).await;
+        if rst.is_ok() {
+            self.mark_write();
+        }
+        rst
+    }
+
+    pub fn fetch_stream<'s>(&'s self, sql: &'s str, params: &[MySQLValue]) -> BoxStream<'s, Result<MySqlRow, MySQLAccessorError>> {
+        self.route_stream(sql).fetch_stream(sql, params)
+    }
+
+    pub fn fetch_stream_as<'s, T>(&'s self, sql: &'s str, params: &[MySQLValue]) -> BoxStream<'s, Result<T, MySQLAccessorError>>
+        where T: for<'r> sqlx::FromRow<'r, MySqlRow> + Send + 's {
+        self.route_stream(sql).fetch_stream_as(sql, params)
+    }
+
+    pub async fn fetch_all_as<T>(&self, sql: &str, params: &[MySQLValue]) -> Result<Vec<T>, MySQLAccessorError>
+        where T: for<'r> sqlx::FromRow<'r, MySqlRow> {
+        let rows = self.do_sql_params(sql, params).await?.unwrap_or_default();
+        decode_rows(rows)
+    }
+
+    pub async fn fetch_one_as<T>(&self, sql: &str, params: &[MySQLValue]) -> Result<T, MySQLAccessorError>
+        where T: for<'r> sqlx::FromRow<'r, MySqlRow> {
+        self.fetch_optional_as(sql, params).await?
+            .ok_or(MySQLAccessorError { err_type: MySQLAccessorErrorType::SqlRowNotFound })
+    }
+
+    pub async fn fetch_optional_as<T>(&self, sql: &str, params: &[MySQLValue]) -> Result<Option<T>, MySQLAccessorError>
+        where T: for<'r> sqlx::FromRow<'r, MySqlRow> {
+        let row = self.fetch_optional_row(sql, params).await?;
+        decode_optional_row(row)
+    }
+
+    pub async fn fetch_scalar<T>(&self, sql: &str, params: &[MySQLValue]) -> Result<T, MySQLAccessorError>
+        where T: for<'r> sqlx::Decode<'r, MySql> + sqlx::Type<MySql> {
+        let row = self.fetch_optional_row(sql, params).await?;
+        decode_scalar(row)
+    }
+}
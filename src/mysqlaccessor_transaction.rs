@@ -0,0 +1,137 @@
+use futures::stream::BoxStream;
+use sqlx::mysql::{MySql, MySqlConnection, MySqlRow, MySqlTransactionManager};
+use sqlx::pool::PoolConnection;
+use sqlx::{Executor, TransactionManager};
+use crate::mysqlaccessor_batch::MySQLBatchInsert;
+use crate::mysqlaccessor::{MySQLAccessorError, MySQLAccessorErrorType, MySQLValue, MySQLExecuteResult, fetch_rows, fetch_optional_row, stream_rows, stream_rows_as, execute_sql, decode_rows, decode_optional_row, decode_scalar};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MySQLIsolationLevel {
+    ReadUncommitted,
+    ReadCommitted,
+    RepeatableRead,
+    Serializable
+}
+
+impl MySQLIsolationLevel {
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            MySQLIsolationLevel::ReadUncommitted => "READ UNCOMMITTED",
+            MySQLIsolationLevel::ReadCommitted => "READ COMMITTED",
+            MySQLIsolationLevel::RepeatableRead => "REPEATABLE READ",
+            MySQLIsolationLevel::Serializable => "SERIALIZABLE"
+        }
+    }
+}
+
+pub(crate) enum MySQLTransactionConn<'c> {
+    Connection(&'c mut MySqlConnection),
+    Pooled(Box<PoolConnection<MySql>>)
+}
+
+impl<'c> MySQLTransactionConn<'c> {
+    fn as_mut(&mut self) -> &mut MySqlConnection {
+        match self {
+            MySQLTransactionConn::Connection(conn) => conn,
+            MySQLTransactionConn::Pooled(conn) => conn
+        }
+    }
+}
+
+pub struct MySQLTransaction<'c> {
+    conn: MySQLTransactionConn<'c>,
+    open: bool
+}
+
+fn map_transaction_err(e: sqlx::Error) -> MySQLAccessorError {
+    MySQLAccessorError { err_type: MySQLAccessorErrorType::TransactionError(e) }
+}
+
+impl<'c> MySQLTransaction<'c> {
+    pub(crate) async fn begin(mut conn: MySQLTransactionConn<'c>, isolation_level: Option<MySQLIsolationLevel>) -> Result<MySQLTransaction<'c>, MySQLAccessorError> {
+        if let Some(isolation_level) = isolation_level {
+            let sql = format!("SET TRANSACTION ISOLATION LEVEL {}", isolation_level.as_sql());
+            conn.as_mut().execute(sql.as_str()).await.map_err(map_transaction_err)?;
+        }
+        MySqlTransactionManager::begin(conn.as_mut()).await.map_err(map_transaction_err)?;
+        Ok(Self {
+            conn,
+            open: true
+        })
+    }
+
+    pub async fn savepoint(&mut self) -> Result<MySQLTransaction<'_>, MySQLAccessorError> {
+        MySQLTransaction::begin(MySQLTransactionConn::Connection(self.conn.as_mut()), None).await
+    }
+
+    pub async fn commit(mut self) -> Result<(), MySQLAccessorError> {
+        MySqlTransactionManager::commit(self.conn.as_mut()).await.map_err(map_transaction_err)?;
+        self.open = false;
+        Ok(())
+    }
+
+    pub async fn rollback(mut self) -> Result<(), MySQLAccessorError> {
+        MySqlTransactionManager::rollback(self.conn.as_mut()).await.map_err(map_transaction_err)?;
+        self.open = false;
+        Ok(())
+    }
+
+    pub async fn do_sql(&mut self, sql: &str) -> Result<Option<Vec<MySqlRow>>, MySQLAccessorError> {
+        self.do_sql_params(sql, &[]).await
+    }
+
+    pub async fn do_sql_params(&mut self, sql: &str, params: &[MySQLValue]) -> Result<Option<Vec<MySqlRow>>, MySQLAccessorError> {
+        let rst = fetch_rows(self.conn.as_mut(), sql, params).await?;
+        Ok(Some(rst))
+    }
+
+    pub async fn execute(&mut self, sql: &str, params: &[MySQLValue]) -> Result<MySQLExecuteResult, MySQLAccessorError> {
+        execute_sql(self.conn.as_mut(), sql, params).await
+    }
+
+    pub async fn batch_insert<I>(&mut self, batch: &MySQLBatchInsert, rows: I) -> Result<u64, MySQLAccessorError>
+        where I: IntoIterator<Item = Vec<MySQLValue>> {
+        batch.execute(self.conn.as_mut(), rows).await
+    }
+
+    pub fn fetch_stream<'s>(&'s mut self, sql: &'s str, params: &[MySQLValue]) -> BoxStream<'s, Result<MySqlRow, MySQLAccessorError>> {
+        stream_rows(self.conn.as_mut(), sql, params)
+    }
+
+    pub fn fetch_stream_as<'s, T>(&'s mut self, sql: &'s str, params: &[MySQLValue]) -> BoxStream<'s, Result<T, MySQLAccessorError>>
+        where T: for<'r> sqlx::FromRow<'r, MySqlRow> + Send + 's {
+        stream_rows_as(self.conn.as_mut(), sql, params)
+    }
+
+    pub async fn fetch_all_as<T>(&mut self, sql: &str, params: &[MySQLValue]) -> Result<Vec<T>, MySQLAccessorError>
+        where T: for<'r> sqlx::FromRow<'r, MySqlRow> {
+        let rows = self.do_sql_params(sql, params).await?.unwrap_or_default();
+        decode_rows(rows)
+    }
+
+    pub async fn fetch_one_as<T>(&mut self, sql: &str, params: &[MySQLValue]) -> Result<T, MySQLAccessorError>
+        where T: for<'r> sqlx::FromRow<'r, MySqlRow> {
+        self.fetch_optional_as(sql, params).await?
+            .ok_or(MySQLAccessorError { err_type: MySQLAccessorErrorType::SqlRowNotFound })
+    }
+
+    pub async fn fetch_optional_as<T>(&mut self, sql: &str, params: &[MySQLValue]) -> Result<Option<T>, MySQLAccessorError>
+        where T: for<'r> sqlx::FromRow<'r, MySqlRow> {
+        let row = fetch_optional_row(self.conn.as_mut(), sql, params).await?;
+        decode_optional_row(row)
+    }
+
+    pub async fn fetch_scalar<T>(&mut self, sql: &str, params: &[MySQLValue]) -> Result<T, MySQLAccessorError>
+        where T: for<'r> sqlx::Decode<'r, MySql> + sqlx::Type<MySql> {
+        let row = fetch_optional_row(self.conn.as_mut(), sql, params).await?;
+        decode_scalar(row)
+    }
+}
+
+impl<'c> Drop for MySQLTransaction<'c> {
+    fn drop(&mut self) {
+        if self.open {
+            MySqlTransactionManager::start_rollback(self.conn.as_mut());
+        }
+    }
+}
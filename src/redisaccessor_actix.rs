@@ -1,5 +1,5 @@
 use actix::prelude::*;
-use actix_redis::{Command, RedisActor, Error};
+use actix_redis::{Command, RedisActor};
 use redis_async::{resp::RespValue, resp_array};
 use crate::redisaccessor::{RedisAccessorError, RedisAccessorErrorType, RedisAccessor};
 
@@ -15,34 +15,40 @@ macro_rules! check_conn_open {
     };
 }
 
-pub struct RedisAccessorActix<'a> {
-    pub(crate) host: &'a str,
+pub struct RedisAccessorActix {
+    pub(crate) host: String,
     pub(crate) port: u16,
-    pub(crate) user: &'a str,
-    pub(crate) passwd: &'a str,
+    pub(crate) user: String,
+    pub(crate) passwd: String,
     pub(crate) db: i64,
 
     pub(crate) addr: Option<Addr<RedisActor>>
 }
 
-impl<'a> RedisAccessor for RedisAccessorActix<'a> {
+impl RedisAccessor for RedisAccessorActix {
 }
 
-impl<'a> RedisAccessorActix<'a> {
+impl Default for RedisAccessorActix {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RedisAccessorActix {
     pub fn new() -> Self {
         Self {
-            host: "localhost",
+            host: String::from("localhost"),
             port: 6380,
-            user: "",
-            passwd: "",
+            user: String::from(""),
+            passwd: String::from(""),
             db: 0,
 
             addr: None
         }
     }
 
-    pub fn host(mut self, host: &'a str) -> Self {
-        self.host = host;
+    pub fn host(mut self, host: &str) -> Self {
+        self.host = host.to_string();
         self
     }
 
@@ -51,13 +57,13 @@ impl<'a> RedisAccessorActix<'a> {
         self
     }
 
-    pub fn user(mut self, user: &'a str) -> Self {
-        self.user = user;
+    pub fn user(mut self, user: &str) -> Self {
+        self.user = user.to_string();
         self
     }
 
-    pub fn passwd(mut self, passwd: &'a str) -> Self {
-        self.passwd = passwd;
+    pub fn passwd(mut self, passwd: &str) -> Self {
+        self.passwd = passwd.to_string();
         self
     }
 
@@ -79,7 +85,7 @@ impl<'a> RedisAccessorActix<'a> {
         let gen_conn_err_closure = || RedisAccessorError {
             err_type: RedisAccessorErrorType::OpenConnError
         };
-        let auth_future = self.addr.as_ref().unwrap().send(Command(resp_array!["AUTH", self.passwd]));
+        let auth_future = self.addr.as_ref().unwrap().send(Command(resp_array!["AUTH", self.passwd.as_str()]));
         let auth_resp = auth_future.await.map_err(|_| gen_conn_err_closure())?.map_err(|_| gen_conn_err_closure())?;
         match auth_resp {
             RespValue::SimpleString(x) => match x.as_str() {
@@ -113,7 +119,7 @@ impl<'a> RedisAccessorActix<'a> {
     ) -> Result<String, RedisAccessorErrorType> {
         match resp_value {
             Ok(RespValue::SimpleString(x)) => {
-                Ok(format!("{}", x))
+                Ok(x.clone())
             },
             Ok(RespValue::Error(x)) => {
                 if x.starts_with("NOAUTH") && !is_last_cmd_auth {
@@ -121,19 +127,19 @@ impl<'a> RedisAccessorActix<'a> {
                     self.send_select_db().await.map_err(|e| e.err_type)?;
                     Err(RedisAccessorErrorType::AuthError)
                 } else {
-                    Ok(format!("{}", x))
+                    Ok(x.clone())
                 }
             },
             Ok(RespValue::Nil) => {
                 Err(RedisAccessorErrorType::GetKeyNotExist)
             },
             Ok(RespValue::BulkString(x)) => {
-                Ok(format!("{}", String::from_utf8_lossy(&x).to_string()))
+                Ok(String::from_utf8_lossy(x).to_string())
             },
             Ok(RespValue::Integer(x)) => {
                 Ok(format!("{}", x))
             },
-            Ok(RespValue::Array(x)) => {
+            Ok(RespValue::Array(_)) => {
                 Ok("".to_string())
             },
             Err(e) => {
@@ -160,7 +166,7 @@ impl<'a> RedisAccessorActix<'a> {
                         match redis_send_result {
                             Ok(resp_value) => match self.match_resp_value(resp_value.as_ref(), true).await {
                                 Ok(s) => Ok(s),
-                                Err(e) => Err(gen_not_exist_closure())
+                                Err(_) => Err(gen_not_exist_closure())
                             }
                             _ => Err(gen_err_closure())
                         }
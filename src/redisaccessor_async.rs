@@ -1,5 +1,5 @@
 use crate::redisaccessor::{RedisAccessorError, RedisAccessorErrorType, RedisAccessor};
-use redis::{Commands, RedisResult};
+use redis::RedisResult;
 use std::collections::HashMap;
 
 macro_rules! check_conn_open {
@@ -14,27 +14,33 @@ macro_rules! check_conn_open {
     };
 }
 
-pub struct RedisAccessorAsync<'a> {
-    pub(crate) host: &'a str,
+pub struct RedisAccessorAsync {
+    pub(crate) host: String,
     pub(crate) port: u16,
-    pub(crate) user: &'a str,
-    pub(crate) passwd: &'a str,
+    pub(crate) user: String,
+    pub(crate) passwd: String,
     pub(crate) db: i64,
 
     client: Option<redis::Client>,
     pub(crate) async_conn: Option<redis::aio::Connection>
 }
 
-impl<'a> RedisAccessor for RedisAccessorAsync<'a> {
+impl RedisAccessor for RedisAccessorAsync {
 }
 
-impl<'a> RedisAccessorAsync<'a> {
+impl Default for RedisAccessorAsync {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RedisAccessorAsync {
     pub fn new() -> Self {
         Self {
-            host: "localhost",
+            host: String::from("localhost"),
             port: 6380,
-            user: "",
-            passwd: "",
+            user: String::from(""),
+            passwd: String::from(""),
             db: 0,
 
             client: None,
@@ -42,8 +48,8 @@ impl<'a> RedisAccessorAsync<'a> {
         }
     }
 
-    pub fn host(mut self, host: &'a str) -> Self {
-        self.host = host;
+    pub fn host(mut self, host: &str) -> Self {
+        self.host = host.to_string();
         self
     }
 
@@ -52,13 +58,13 @@ impl<'a> RedisAccessorAsync<'a> {
         self
     }
 
-    pub fn user(mut self, user: &'a str) -> Self {
-        self.user = user;
+    pub fn user(mut self, user: &str) -> Self {
+        self.user = user.to_string();
         self
     }
 
-    pub fn passwd(mut self, passwd: &'a str) -> Self {
-        self.passwd = passwd;
+    pub fn passwd(mut self, passwd: &str) -> Self {
+        self.passwd = passwd.to_string();
         self
     }
 
@@ -69,26 +75,23 @@ impl<'a> RedisAccessorAsync<'a> {
 
     pub async fn open_connection(&mut self) -> Result<(), RedisAccessorError> {
         if self.async_conn.is_none() {
-            let map_redis_err = |e| RedisAccessorError { err_type: RedisAccessorErrorType::OpenConnError };
+            let map_redis_err = |_| RedisAccessorError { err_type: RedisAccessorErrorType::OpenConnError };
             // let connect_uri = format!("redis://{}:{}", self.host, self.port)?;
             let mut connection_info = redis::ConnectionInfo {
-                addr: Box::new(redis::ConnectionAddr::Tcp(String::from(self.host), self.port)),
+                addr: Box::new(redis::ConnectionAddr::Tcp(self.host.clone(), self.port)),
                 db: self.db,
                 username: None,
                 passwd: None
             };
-            if self.user != "" {
-                connection_info.username = Some(String::from(self.user));
+            if !self.user.is_empty() {
+                connection_info.username = Some(self.user.clone());
             }
-            if self.passwd != "" {
-                connection_info.passwd = Some(String::from(self.passwd));
+            if !self.passwd.is_empty() {
+                connection_info.passwd = Some(self.passwd.clone());
             }
             let client = redis::Client::open(connection_info).map_err(map_redis_err)?;
             self.client = Some(client);
-            self.async_conn = match self.client.as_ref().unwrap().get_async_connection().await {
-                Ok(c) => Some(c),
-                Err(_e) => None
-            };
+            self.async_conn = self.client.as_ref().unwrap().get_async_connection().await.ok();
         }
         Ok(())
     }
@@ -97,7 +100,7 @@ impl<'a> RedisAccessorAsync<'a> {
         let redis_rst: RedisResult<()> = pipe.query_async(async_conn).await;
         match redis_rst {
             Ok(_) => Ok(()),
-            Err(e) => Err(RedisAccessorError {
+            Err(_) => Err(RedisAccessorError {
                 err_type: RedisAccessorErrorType::SetContentError
             })
         }
@@ -109,7 +112,7 @@ impl<'a> RedisAccessorAsync<'a> {
         let rst: RedisResult<T> = redis::cmd("GET").arg(key).query_async(self.async_conn.as_mut().unwrap()).await;
         match rst {
             Ok(r) => Ok(r),
-            Err(e) => Err(RedisAccessorError {
+            Err(_) => Err(RedisAccessorError {
                 err_type: RedisAccessorErrorType::GetContentError
             })
         }
@@ -120,7 +123,7 @@ impl<'a> RedisAccessorAsync<'a> {
         let redis_rst: RedisResult<redis::Value> = redis::cmd("SETEX").arg(key).arg(ex).arg(val).query_async(self.async_conn.as_mut().unwrap()).await;
         match redis_rst {
             Ok(_) => Ok(()),
-            Err(e) => Err(RedisAccessorError {
+            Err(_) => Err(RedisAccessorError {
                 err_type: RedisAccessorErrorType::SetContentError
             })
         }
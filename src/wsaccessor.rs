@@ -0,0 +1,386 @@
+use std::sync::Arc;
+use tokio::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use futures::{SinkExt, StreamExt};
+use futures::stream::BoxStream;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use tokio_tungstenite::tungstenite::Message;
+
+type WsStream = tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+#[derive(Debug)]
+pub struct WsAccessorError {
+    pub err_type: WsAccessorErrorType
+}
+
+#[derive(Debug)]
+pub enum WsAccessorErrorType {
+    ConnNotOpen,
+    ConnClosed,
+    OpenConnError(tokio_tungstenite::tungstenite::Error),
+    ReceiveError(tokio_tungstenite::tungstenite::Error),
+    SerializeError(serde_json::Error),
+    DecodeError(serde_json::Error)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WsMessage {
+    Text(String),
+    Binary(Vec<u8>)
+}
+
+impl WsMessage {
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            WsMessage::Text(text) => Some(text.as_str()),
+            WsMessage::Binary(_) => None
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            WsMessage::Text(text) => text.as_bytes(),
+            WsMessage::Binary(data) => data.as_slice()
+        }
+    }
+
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, WsAccessorError> {
+        serde_json::from_slice(self.as_bytes())
+            .map_err(|e| WsAccessorError { err_type: WsAccessorErrorType::DecodeError(e) })
+    }
+
+    fn into_message(self) -> Message {
+        match self {
+            WsMessage::Text(text) => Message::Text(text),
+            WsMessage::Binary(data) => Message::Binary(data)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum WsReconnectEvent<'e> {
+    Lost(Option<&'e WsAccessorError>),
+    /// Messages pushed here are sent first on the new connection, before anything queued while disconnected.
+    Reconnected(&'e mut Vec<WsMessage>),
+    ReconnectFailed(&'e WsAccessorError)
+}
+
+pub type WsReconnectCallback = Arc<dyn Fn(WsReconnectEvent<'_>) + Send + Sync>;
+
+#[derive(Clone)]
+pub struct WsAccessorSender {
+    sender: mpsc::Sender<WsMessage>
+}
+
+impl WsAccessorSender {
+    pub async fn send(&mut self, message: WsMessage) -> Result<(), WsAccessorError> {
+        self.sender.send(message).await
+            .map_err(|_| WsAccessorError { err_type: WsAccessorErrorType::ConnClosed })
+    }
+
+    pub async fn send_text(&mut self, text: &str) -> Result<(), WsAccessorError> {
+        self.send(WsMessage::Text(text.to_string())).await
+    }
+
+    pub async fn send_binary(&mut self, data: Vec<u8>) -> Result<(), WsAccessorError> {
+        self.send(WsMessage::Binary(data)).await
+    }
+
+    pub async fn send_json<T: Serialize>(&mut self, val: &T) -> Result<(), WsAccessorError> {
+        let text = serde_json::to_string(val)
+            .map_err(|e| WsAccessorError { err_type: WsAccessorErrorType::SerializeError(e) })?;
+        self.send(WsMessage::Text(text)).await
+    }
+}
+
+struct WsConnectionConfig {
+    url: String,
+    ping_interval: u32,
+    pong_timeout: u32,
+    reconnect: bool,
+    reconnect_interval: u64,
+    max_reconnect_interval: u64,
+    on_reconnect: Option<WsReconnectCallback>
+}
+
+impl WsConnectionConfig {
+    fn notify(&self, event: WsReconnectEvent<'_>) {
+        if let Some(on_reconnect) = self.on_reconnect.as_ref() {
+            on_reconnect(event);
+        }
+    }
+}
+
+async fn next_ping(ping_timer: &mut Option<tokio::time::Interval>) {
+    match ping_timer.as_mut() {
+        Some(ping_timer) => {
+            ping_timer.tick().await;
+        },
+        None => futures::future::pending().await
+    }
+}
+
+enum WsConnectionEnd {
+    Shutdown,
+    Lost(Option<WsAccessorError>)
+}
+
+pub struct WsAccessor {
+    pub(crate) url: String,
+    pub(crate) ping_interval: u32,
+    pub(crate) pong_timeout: u32,
+    pub(crate) reconnect: bool,
+    pub(crate) reconnect_interval: u64,
+    pub(crate) max_reconnect_interval: u64,
+    pub(crate) channel_size: usize,
+    pub(crate) on_reconnect: Option<WsReconnectCallback>,
+
+    sender: Option<WsAccessorSender>,
+    receiver: Option<mpsc::Receiver<Result<WsMessage, WsAccessorError>>>
+}
+
+impl Default for WsAccessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WsAccessor {
+    pub fn new() -> Self {
+        Self {
+            url: String::from("ws://localhost"),
+            ping_interval: 30,
+            pong_timeout: 10,
+            reconnect: true,
+            reconnect_interval: 500,
+            max_reconnect_interval: 30000,
+            channel_size: 1024,
+            on_reconnect: None,
+            sender: None,
+            receiver: None
+        }
+    }
+
+    pub fn url(mut self, url: &str) -> Self {
+        self.url = url.to_string();
+        self
+    }
+
+    pub fn ping_interval(mut self, ping_interval: u32) -> Self {
+        self.ping_interval = ping_interval;
+        self
+    }
+
+    pub fn pong_timeout(mut self, pong_timeout: u32) -> Self {
+        self.pong_timeout = pong_timeout;
+        self
+    }
+
+    pub fn reconnect(mut self, reconnect: bool) -> Self {
+        self.reconnect = reconnect;
+        self
+    }
+
+    pub fn reconnect_interval(mut self, reconnect_interval: u64) -> Self {
+        self.reconnect_interval = reconnect_interval;
+        self
+    }
+
+    pub fn max_reconnect_interval(mut self, max_reconnect_interval: u64) -> Self {
+        self.max_reconnect_interval = max_reconnect_interval;
+        self
+    }
+
+    pub fn channel_size(mut self, channel_size: usize) -> Self {
+        self.channel_size = channel_size;
+        self
+    }
+
+    pub fn on_reconnect<F>(mut self, on_reconnect: F) -> Self
+        where F: Fn(WsReconnectEvent<'_>) + Send + Sync + 'static {
+        self.on_reconnect = Some(Arc::new(on_reconnect));
+        self
+    }
+
+    async fn connect(url: &str) -> Result<WsStream, WsAccessorError> {
+        let (ws_stream, _) = tokio_tungstenite::connect_async(url).await
+            .map_err(|e| WsAccessorError { err_type: WsAccessorErrorType::OpenConnError(e) })?;
+        Ok(ws_stream)
+    }
+
+    pub async fn open_connection(&mut self) -> Result<(), WsAccessorError> {
+        if self.sender.is_none() {
+            let ws_stream = Self::connect(self.url.as_str()).await?;
+            let (out_tx, out_rx) = mpsc::channel(self.channel_size);
+            let (in_tx, in_rx) = mpsc::channel(self.channel_size);
+            let config = WsConnectionConfig {
+                url: self.url.clone(),
+                ping_interval: self.ping_interval,
+                pong_timeout: self.pong_timeout,
+                reconnect: self.reconnect,
+                reconnect_interval: self.reconnect_interval,
+                max_reconnect_interval: self.max_reconnect_interval,
+                on_reconnect: self.on_reconnect.clone()
+            };
+            tokio::spawn(Self::run(config, ws_stream, out_rx, in_tx));
+            self.sender = Some(WsAccessorSender {
+                sender: out_tx
+            });
+            self.receiver = Some(in_rx);
+        }
+        Ok(())
+    }
+
+    async fn run(
+        config: WsConnectionConfig,
+        mut ws_stream: WsStream,
+        mut out_rx: mpsc::Receiver<WsMessage>,
+        mut in_tx: mpsc::Sender<Result<WsMessage, WsAccessorError>>
+    ) {
+        let mut pending = Vec::new();
+        loop {
+            let cause = match Self::run_connection(&config, ws_stream, &mut pending, &mut out_rx, &mut in_tx).await {
+                WsConnectionEnd::Shutdown => return,
+                WsConnectionEnd::Lost(cause) => cause
+            };
+            if !config.reconnect {
+                if let Some(cause) = cause {
+                    let _ = in_tx.send(Err(cause)).await;
+                }
+                let _ = in_tx.send(Err(WsAccessorError { err_type: WsAccessorErrorType::ConnClosed })).await;
+                return;
+            }
+            config.notify(WsReconnectEvent::Lost(cause.as_ref()));
+            let mut reconnect_interval = config.reconnect_interval;
+            ws_stream = loop {
+                tokio::time::delay_for(Duration::from_millis(reconnect_interval)).await;
+                loop {
+                    match out_rx.try_recv() {
+                        Ok(message) => pending.push(message),
+                        Err(mpsc::error::TryRecvError::Closed) => return,
+                        Err(mpsc::error::TryRecvError::Empty) => break
+                    }
+                }
+                match Self::connect(config.url.as_str()).await {
+                    Ok(ws_stream) => break ws_stream,
+                    Err(e) => {
+                        log::warn!("websocket reconnect to {} fail: {:?}", config.url, e);
+                        config.notify(WsReconnectEvent::ReconnectFailed(&e));
+                        reconnect_interval = std::cmp::min(reconnect_interval * 2, config.max_reconnect_interval);
+                    }
+                }
+            };
+            log::info!("websocket reconnected to {}", config.url);
+            let mut resubscribe = Vec::new();
+            config.notify(WsReconnectEvent::Reconnected(&mut resubscribe));
+            pending.splice(0..0, resubscribe);
+        }
+    }
+
+    async fn run_connection(
+        config: &WsConnectionConfig,
+        ws_stream: WsStream,
+        pending: &mut Vec<WsMessage>,
+        out_rx: &mut mpsc::Receiver<WsMessage>,
+        in_tx: &mut mpsc::Sender<Result<WsMessage, WsAccessorError>>
+    ) -> WsConnectionEnd {
+        let (mut sink, mut stream) = ws_stream.split();
+        for message in pending.drain(..) {
+            if sink.send(message.into_message()).await.is_err() {
+                return WsConnectionEnd::Lost(None);
+            }
+        }
+        let ping_period = Duration::from_secs(config.ping_interval as u64);
+        let mut ping_timer = match config.ping_interval {
+            0 => None,
+            _ => Some(tokio::time::interval_at(Instant::now() + ping_period, ping_period))
+        };
+        let mut last_seen = Instant::now();
+        loop {
+            tokio::select! {
+                msg = stream.next() => {
+                    last_seen = Instant::now();
+                    let forward = match msg {
+                        Some(Ok(Message::Text(text))) => Ok(WsMessage::Text(text)),
+                        Some(Ok(Message::Binary(data))) => Ok(WsMessage::Binary(data)),
+                        Some(Ok(Message::Ping(_))) | Some(Ok(Message::Pong(_))) => continue,
+                        Some(Ok(Message::Close(_))) | None => return WsConnectionEnd::Lost(None),
+                        Some(Err(e)) => {
+                            log::warn!("websocket receive from {} fail: {:?}", config.url, e);
+                            return WsConnectionEnd::Lost(Some(WsAccessorError { err_type: WsAccessorErrorType::ReceiveError(e) }));
+                        }
+                    };
+                    if in_tx.send(forward).await.is_err() {
+                        let _ = sink.send(Message::Close(None)).await;
+                        return WsConnectionEnd::Shutdown;
+                    }
+                },
+                out = out_rx.recv() => {
+                    let message = match out {
+                        Some(message) => message.into_message(),
+                        None => {
+                            let _ = sink.send(Message::Close(None)).await;
+                            return WsConnectionEnd::Shutdown;
+                        }
+                    };
+                    if sink.send(message).await.is_err() {
+                        return WsConnectionEnd::Lost(None);
+                    }
+                },
+                _ = next_ping(&mut ping_timer) => {
+                    if last_seen.elapsed() > ping_period + Duration::from_secs(config.pong_timeout as u64) {
+                        log::warn!("websocket {} pong timeout", config.url);
+                        return WsConnectionEnd::Lost(None);
+                    }
+                    if sink.send(Message::Ping(Vec::new())).await.is_err() {
+                        return WsConnectionEnd::Lost(None);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn sender(&self) -> Result<WsAccessorSender, WsAccessorError> {
+        self.sender.clone().ok_or(WsAccessorError { err_type: WsAccessorErrorType::ConnNotOpen })
+    }
+
+    pub async fn send_text(&mut self, text: &str) -> Result<(), WsAccessorError> {
+        self.sender()?.send_text(text).await
+    }
+
+    pub async fn send_binary(&mut self, data: Vec<u8>) -> Result<(), WsAccessorError> {
+        self.sender()?.send_binary(data).await
+    }
+
+    pub async fn send_json<T: Serialize>(&mut self, val: &T) -> Result<(), WsAccessorError> {
+        self.sender()?.send_json(val).await
+    }
+
+    pub async fn next_message(&mut self) -> Option<Result<WsMessage, WsAccessorError>> {
+        match self.receiver.as_mut() {
+            Some(receiver) => receiver.recv().await,
+            None => Some(Err(WsAccessorError { err_type: WsAccessorErrorType::ConnNotOpen }))
+        }
+    }
+
+    pub async fn next_json<T: DeserializeOwned>(&mut self) -> Option<Result<T, WsAccessorError>> {
+        self.next_message().await.map(|message| message.and_then(|message| message.json()))
+    }
+
+    pub fn into_stream(self) -> BoxStream<'static, Result<WsMessage, WsAccessorError>> {
+        match self.receiver {
+            Some(receiver) => receiver.boxed(),
+            None => futures::stream::iter(vec![Err(WsAccessorError { err_type: WsAccessorErrorType::ConnNotOpen })]).boxed()
+        }
+    }
+
+    pub fn into_json_stream<T: DeserializeOwned + Send + 'static>(self) -> BoxStream<'static, Result<T, WsAccessorError>> {
+        self.into_stream().map(|message| message.and_then(|message| message.json())).boxed()
+    }
+
+    pub fn close(&mut self) {
+        self.sender = None;
+        self.receiver = None;
+    }
+}
@@ -1,6 +1,5 @@
 use std::collections::HashMap;
 use std::fs;
-use serde_json;
 use once_cell::sync::Lazy;
 
 pub static ENV_CONFIG: Lazy<HashMap<String, String>> = Lazy::new(|| {
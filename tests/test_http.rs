@@ -1,16 +1,37 @@
+mod test_server;
+
 use rustcommon::httpaccessor;
+use rustcommon::httpaccessor_upstream::{HttpAccessorUpstream, HttpAccessorBalanceStrategy};
+use rustcommon::httpaccessor_transport::HttpTransportErrorKind;
+use rustcommon::httpaccessor_mock::{HttpMockTransport, HttpMockRoute};
+use rustcommon::httpaccessor_cassette::HttpCassetteTransport;
+use rustcommon::httpaccessor_graphql::{GraphQLAccessor, GraphQLAccessorErrorType, GraphQLPathSegment};
+use rustcommon::httpaccessor_pagination::{HttpPaginator, HttpPaginationStrategy, HttpPaginationErrorType};
+use rustcommon::httpaccessor_tracing::{HttpTraceContext, HttpTracePropagation, HttpTraceSubscriber};
+use rustcommon::httpaccessor_ratelimit::HttpRateLimiter;
+use rustcommon::httpaccessor_compression::HttpCompression;
+use rustcommon::httpaccessor_hyper::HttpProtocol;
+use rustcommon::httpaccessor_resolver::{HttpResolver, HttpCachingResolver};
+use rustcommon::httpaccessor_jsonrpc::{JsonRpcAccessor, JsonRpcAccessorErrorType, JsonRpcBatch, JsonRpcErrorKind};
+
+use futures::StreamExt;
 
-use tokio;
+fn get_http_client_test(mock: &HttpMockTransport) -> httpaccessor::HttpAccessor {
+    httpaccessor::HttpAccessor::new()
+        .timeout(10)
+        .transport(mock.clone())
+}
 
 #[tokio::test]
 async fn test_http_async_get() -> Result<(), String> {
-    let resp_wrapper_result = httpaccessor::HttpAccessor::async_get("http://www.baidu.com", 10).await;
+    let base_url = test_server::spawn_http_server(200, "static").await;
+    let resp_wrapper_result = httpaccessor::HttpAccessor::async_get(format!("{}/", base_url).as_str(), 10).await;
     match resp_wrapper_result {
-        Ok(resp) => match resp.status_code() {
-            200 => Ok(()),
+        Ok(resp) => match (resp.status_code(), resp.content()) {
+            (200, "static") => Ok(()),
             _ => Err(String::from("do http_async_get fail"))
         },
-        Err(e) => {
+        Err(_) => {
             Err(String::from("do http_async_get fail"))
         }
     }
@@ -18,22 +39,630 @@ async fn test_http_async_get() -> Result<(), String> {
 
 #[tokio::test]
 async fn test_http_async_multi_get() -> Result<(), String> {
-    let resp_wrapper_list_result = httpaccessor::HttpAccessor::async_multi_get(&vec!["http://www.baidu.com", "http://www.taobao.com"], 10).await;
+    let first = format!("{}/", test_server::spawn_http_server(200, "first").await);
+    let second = format!("{}/", test_server::spawn_http_server(200, "second").await);
+    let resp_wrapper_list_result = httpaccessor::HttpAccessor::async_multi_get(&[first.as_str(), second.as_str()], 10).await;
+    match resp_wrapper_list_result {
+        Ok(resp_result_list) => {
+            let contents: Vec<String> = resp_result_list.into_iter()
+                .filter_map(|resp_result| resp_result.ok())
+                .filter(|resp| resp.status_code() == 200)
+                .map(|resp| resp.content().to_string())
+                .collect();
+            match contents == vec!["first", "second"] {
+                true => Ok(()),
+                false => Err(String::from("do http_async_multi_get fail"))
+            }
+        },
+        Err(_) => Err(String::from("do http_async_multi_get fail"))
+    }
+}
+
+#[tokio::test]
+async fn test_http_transport_get() -> Result<(), String> {
+    let mock = HttpMockTransport::new()
+        .route(HttpMockRoute::get("http://www.baidu.com").respond(200, "baidu"));
+    let resp_wrapper_result = get_http_client_test(&mock).get("http://www.baidu.com").await;
+    match resp_wrapper_result {
+        Ok(resp) => match resp.status_code() {
+            200 => Ok(()),
+            _ => Err(String::from("do http_transport_get fail"))
+        },
+        Err(_) => {
+            Err(String::from("do http_transport_get fail"))
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_http_transport_multi_get() -> Result<(), String> {
+    let mock = HttpMockTransport::new()
+        .route(HttpMockRoute::get("http://www.baidu.com").respond(200, "baidu"))
+        .route(HttpMockRoute::get("http://www.taobao.com").respond(200, "taobao").delay(50));
+    let resp_wrapper_list_result = get_http_client_test(&mock).multi_get(&["http://www.baidu.com", "http://www.taobao.com"]).await;
     match resp_wrapper_list_result {
         Ok(resp_result_list) => {
             match resp_result_list.into_iter().all(|resp_result| {
                 match resp_result {
-                    Ok(resp) => match resp.status_code() {
-                        200 => true,
-                        _ => false
-                    }
+                    Ok(resp) => resp.status_code() == 200,
                     _ => false
                 }
             }) {
                 true => Ok(()),
-                false => Err(String::from("do http_async_multi_get fail"))
+                false => Err(String::from("do http_transport_multi_get fail"))
             }
         },
-        Err(e) => Err(String::from("do http_async_multi_get fail"))
+        Err(_) => Err(String::from("do http_transport_multi_get fail"))
+    }
+}
+
+#[tokio::test]
+async fn test_http_mock_transport() -> Result<(), String> {
+    let mock = HttpMockTransport::new()
+        .route(HttpMockRoute::post("/api/*").body_contains("ping").times(1).error(HttpTransportErrorKind::Other))
+        .route(HttpMockRoute::post("/api/*").body_contains("ping").respond(201, "pong").header("X-Mock", "1"))
+        .route(HttpMockRoute::get("/slow").respond(200, "slow").delay(3000))
+        .route(HttpMockRoute::get("/headers").header("X-Mock", "before").respond(200, "headers"))
+        .route(HttpMockRoute::get("/recovered").error(HttpTransportErrorKind::Other).header("X-Mock", "after").respond(200, "recovered"));
+    let http_accessor = get_http_client_test(&mock);
+    for (path, expected) in [("/headers", "before"), ("/recovered", "after")].iter() {
+        let resp = http_accessor.get(format!("http://mock.local{}", path).as_str()).await
+            .map_err(|_| String::from("do http_mock_transport fail"))?;
+        if resp.header_value("x-mock") != Some(*expected) {
+            return Err(String::from("do http_mock_transport fail, route header dropped"));
+        }
+    }
+    if http_accessor.post("http://mock.local/api/echo", "ping").await.is_ok() {
+        return Err(String::from("do http_mock_transport fail, error not injected"));
+    }
+    let resp = http_accessor.post("http://mock.local/api/echo", "ping").await
+        .map_err(|_| String::from("do http_mock_transport fail"))?;
+    if resp.status_code() != 201 || resp.content() != "pong" || resp.header_value("x-mock") != Some("1") {
+        return Err(String::from("do http_mock_transport fail, wrong response"));
+    }
+    let slow_request = httpaccessor::HttpAccessorRequest::get("http://mock.local/slow").timeout(1);
+    if http_accessor.execute(slow_request).await.is_ok() {
+        return Err(String::from("do http_mock_transport fail, delay not timed out"));
+    }
+    if http_accessor.get("http://mock.local/missing").await.is_ok() {
+        return Err(String::from("do http_mock_transport fail, unmatched route answered"));
+    }
+    let requests = mock.requests();
+    match requests.len() == 6 && requests[3].body_bytes() == Some(&b"ping"[..]) {
+        true => Ok(()),
+        false => Err(String::from("do http_mock_transport fail, requests not recorded"))
+    }
+}
+
+#[tokio::test]
+async fn test_http_upstream_failover() -> Result<(), String> {
+    let dead = test_server::dead_base_url().await;
+    let live = test_server::spawn_http_server(200, "ok").await;
+    let upstream = HttpAccessorUpstream::new(&[dead.as_str(), live.as_str()])
+        .strategy(HttpAccessorBalanceStrategy::RoundRobin)
+        .max_fails(1);
+    let http_accessor = httpaccessor::HttpAccessor::new()
+        .timeout(5)
+        .upstream(upstream.clone());
+    for _ in 0..4 {
+        match http_accessor.get("/ping").await {
+            Ok(resp) if resp.status_code() == 200 && resp.content() == "ok" => {},
+            _ => return Err(String::from("do http_upstream_failover fail"))
+        }
+    }
+    match upstream.healthy_base_urls() == vec![live.as_str()] {
+        true => Ok(()),
+        false => Err(String::from("do http_upstream_failover fail, dead instance not ejected"))
+    }
+}
+
+#[tokio::test]
+async fn test_http_upstream_consistent_hash() -> Result<(), String> {
+    let first = test_server::spawn_http_server(200, "first").await;
+    let second = test_server::spawn_http_server(200, "second").await;
+    let http_accessor = httpaccessor::HttpAccessor::new()
+        .timeout(5)
+        .upstream(HttpAccessorUpstream::new(&[first.as_str(), second.as_str()])
+            .strategy(HttpAccessorBalanceStrategy::ConsistentHash));
+    let resp_list = http_accessor.multi_get(&["/user/42"; 4]).await
+        .map_err(|_| String::from("do http_upstream_consistent_hash fail"))?;
+    let contents: Vec<String> = resp_list.into_iter()
+        .filter_map(|resp_result| resp_result.ok())
+        .map(|resp| resp.content().to_string())
+        .collect();
+    match contents.len() == 4 && contents.iter().all(|content| content == &contents[0]) {
+        true => Ok(()),
+        false => Err(String::from("do http_upstream_consistent_hash fail"))
+    }
+}
+
+#[tokio::test]
+async fn test_http_cassette_record_replay() -> Result<(), String> {
+    let cassette_path = std::env::temp_dir().join(format!("rustcommon_cassette_{}.json", std::process::id()));
+    let cassette_path = cassette_path.to_str().unwrap();
+    let mock = HttpMockTransport::new()
+        .route(HttpMockRoute::post("/search").body_contains("rust").respond(200, "found").header("Set-Cookie", "sid=1"));
+    let recorder = get_http_client_test(&mock)
+        .cassette(HttpCassetteTransport::record(cassette_path));
+    let request = httpaccessor::HttpAccessorRequest::post("http://api.local/search", "q=rust")
+        .header("Authorization", "Bearer secret");
+    let binary_request = httpaccessor::HttpAccessorRequest::post("http://api.local/search", "")
+        .body(vec![0xff, 0xfe, b'r', b'u', b's', b't']);
+    recorder.execute(request.clone()).await.map_err(|_| String::from("do http_cassette record fail"))?;
+    recorder.execute(binary_request.clone()).await.map_err(|_| String::from("do http_cassette record fail"))?;
+    let cassette = std::fs::read_to_string(cassette_path).map_err(|_| String::from("do http_cassette fail, not written"))?;
+    if cassette.contains("secret") || cassette.contains("sid=1") {
+        return Err(String::from("do http_cassette fail, auth header not redacted"));
+    }
+    if !cassette.contains("\"body_encoding\": \"base64\"") || !cassette.contains("//5ydXN0") {
+        return Err(String::from("do http_cassette fail, binary body not base64 encoded"));
+    }
+    let unattached = httpaccessor::HttpAccessor::new()
+        .transport(HttpCassetteTransport::record(cassette_path));
+    if unattached.execute(request.clone()).await.is_ok() {
+        return Err(String::from("do http_cassette fail, recorded without inner transport"));
+    }
+    let replayer = httpaccessor::HttpAccessor::new()
+        .transport(HttpCassetteTransport::replay(cassette_path).map_err(|_| String::from("do http_cassette load fail"))?);
+    let resp = replayer.execute(request).await.map_err(|_| String::from("do http_cassette replay fail"))?;
+    let binary_resp = replayer.execute(binary_request).await.map_err(|_| String::from("do http_cassette binary replay fail"))?;
+    let lossy_request = httpaccessor::HttpAccessorRequest::post("http://api.local/search", "")
+        .body(vec![0xfd, 0xfe, b'r', b'u', b's', b't']);
+    let lossy_mismatch = replayer.execute(lossy_request).await;
+    let mismatch = replayer.post("http://api.local/search", "q=go").await;
+    let _ = std::fs::remove_file(cassette_path);
+    match resp.status_code() == 200 && resp.content() == "found" && binary_resp.content() == "found"
+        && lossy_mismatch.is_err() && mismatch.is_err() {
+        true => Ok(()),
+        false => Err(String::from("do http_cassette replay fail, wrong response"))
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct GraphQLTestUser {
+    name: String
+}
+
+#[derive(serde::Deserialize)]
+struct GraphQLTestData {
+    user: GraphQLTestUser
+}
+
+#[tokio::test]
+async fn test_http_graphql() -> Result<(), String> {
+    let mock = HttpMockTransport::new()
+        .route(HttpMockRoute::post("/graphql").body_contains("\"operationName\":\"GetUser\"")
+            .respond(200, r#"{"data": {"user": {"name": "plum"}}}"#))
+        .route(HttpMockRoute::post("/graphql")
+            .respond(200, r#"{"data": null, "errors": [{"message": "denied", "path": ["user", 0], "extensions": {"code": "FORBIDDEN"}}]}"#));
+    let graphql_accessor = GraphQLAccessor::new(get_http_client_test(&mock), "http://gateway.local/graphql");
+    let variables = serde_json::json!({"id": 1});
+    let data: GraphQLTestData = graphql_accessor
+        .execute("query GetUser($id: ID!) { user(id: $id) { name } }", Some(&variables), Some("GetUser")).await
+        .map_err(|_| String::from("do http_graphql fail"))?;
+    if data.user.name != "plum" {
+        return Err(String::from("do http_graphql fail, wrong data"));
+    }
+    match graphql_accessor.query::<GraphQLTestData>("{ user { name } }").await {
+        Err(e) => match e.err_type {
+            GraphQLAccessorErrorType::GraphQLErrors(errors, _) => {
+                match errors[0].path == vec![GraphQLPathSegment::Key("user".to_string()), GraphQLPathSegment::Index(0)]
+                    && errors[0].extensions.as_ref().and_then(|ext| ext.get("code")).and_then(|code| code.as_str()) == Some("FORBIDDEN") {
+                    true => Ok(()),
+                    false => Err(String::from("do http_graphql fail, wrong error"))
+                }
+            },
+            _ => Err(String::from("do http_graphql fail, wrong error type"))
+        },
+        Ok(_) => Err(String::from("do http_graphql fail, errors ignored"))
+    }
+}
+
+#[tokio::test]
+async fn test_http_jsonrpc() -> Result<(), String> {
+    let mock = HttpMockTransport::new()
+        .route(HttpMockRoute::post("/rpc").body_contains("\"add\"").times(1).error(HttpTransportErrorKind::Connect))
+        .route(HttpMockRoute::post("/rpc").body_contains("\"add\"").respond(200, r#"{"jsonrpc": "2.0", "id": 1, "result": 3}"#))
+        .route(HttpMockRoute::post("/rpc").body_contains("\"missing\"")
+            .respond(200, r#"{"jsonrpc": "2.0", "id": 2, "error": {"code": -32601, "message": "Method not found"}}"#))
+        .route(HttpMockRoute::post("/rpc").body_contains("[")
+            .respond(200, r#"[{"jsonrpc": "2.0", "id": 4, "error": {"code": 7, "message": "busy"}}, {"jsonrpc": "2.0", "id": 3, "result": "a"}]"#));
+    let jsonrpc_accessor = JsonRpcAccessor::new(get_http_client_test(&mock).retries(1).retry_interval(1), "http://rpc.local/rpc");
+    let sum: i64 = jsonrpc_accessor.call("add", &(1, 2)).await.map_err(|_| String::from("do http_jsonrpc call fail"))?;
+    if sum != 3 {
+        return Err(String::from("do http_jsonrpc call fail, wrong result"));
+    }
+    match jsonrpc_accessor.call::<i64, _>("missing", &()).await {
+        Err(e) => match e.err_type {
+            JsonRpcAccessorErrorType::RpcError(err) if err.kind() == JsonRpcErrorKind::MethodNotFound => {},
+            _ => return Err(String::from("do http_jsonrpc fail, wrong error type"))
+        },
+        Ok(_) => return Err(String::from("do http_jsonrpc fail, error ignored"))
+    }
+    let batch = JsonRpcBatch::new()
+        .call("first", serde_json::json!([]))
+        .notify("log", serde_json::json!(["x"]))
+        .call("second", serde_json::json!([]));
+    if batch.len() != 3 || batch.call_count() != 2 || batch.notification_count() != 1 {
+        return Err(String::from("do http_jsonrpc batch fail, wrong counts"));
+    }
+    let call_count = batch.call_count();
+    let rst = jsonrpc_accessor.batch_call(batch).await.map_err(|_| String::from("do http_jsonrpc batch fail"))?;
+    match rst.len() == call_count
+        && rst[0].as_ref().ok() == Some(&serde_json::json!("a"))
+        && rst[1].as_ref().err().map(|err| err.code) == Some(7) {
+        true => Ok(()),
+        false => Err(String::from("do http_jsonrpc batch fail, wrong order"))
+    }
+}
+
+#[tokio::test]
+async fn test_http_pagination() -> Result<(), String> {
+    let mock = HttpMockTransport::new()
+        .route(HttpMockRoute::get("http://api.local/items").respond(200, "[1, 2]")
+            .header("Link", r#"<http://api.local/items?page=2>; rel="next", <http://api.local/items>; rel="first""#))
+        .route(HttpMockRoute::get("http://api.local/items?page=2").times(1).respond(503, ""))
+        .route(HttpMockRoute::get("http://api.local/items?page=2").respond(200, "[3]"))
+        .route(HttpMockRoute::get("http://api.local/feed").respond(200, r#"{"data": [1], "next": "c1"}"#))
+        .route(HttpMockRoute::get("http://api.local/feed?cursor=c1").respond(200, r#"{"data": [2], "next": "c1"}"#));
+    let link_items: Vec<u32> = HttpPaginator::new(get_http_client_test(&mock).retry_interval(1), "http://api.local/items", HttpPaginationStrategy::LinkHeader)
+        .page_retries(1)
+        .items::<u32>()
+        .collect::<Vec<_>>().await
+        .into_iter()
+        .collect::<Result<Vec<u32>, _>>()
+        .map_err(|_| String::from("do http_pagination link fail"))?;
+    if link_items != vec![1, 2, 3] {
+        return Err(String::from("do http_pagination link fail, wrong items"));
+    }
+    let cursor_strategy = HttpPaginationStrategy::Cursor {
+        cursor_param: "cursor".to_string(),
+        cursor_pointer: "/next".to_string()
+    };
+    let cursor_items: Vec<_> = HttpPaginator::new(get_http_client_test(&mock), "http://api.local/feed", cursor_strategy)
+        .items_pointer("/data")
+        .max_pages(3)
+        .items::<u32>()
+        .collect().await;
+    match cursor_items.len() == 4 && cursor_items[..3].iter().all(|item| item.is_ok()) {
+        true => match &cursor_items[3] {
+            Err(e) => match e.err_type {
+                HttpPaginationErrorType::MaxPagesExceeded(3) => Ok(()),
+                _ => Err(String::from("do http_pagination cursor fail, wrong error"))
+            },
+            Ok(_) => Err(String::from("do http_pagination cursor fail, max pages ignored"))
+        },
+        false => Err(String::from("do http_pagination cursor fail, wrong items"))
+    }
+}
+
+#[tokio::test]
+async fn test_http_trace_propagation() -> Result<(), String> {
+    let mock = HttpMockTransport::new()
+        .route(HttpMockRoute::get("http://trace.local/").respond(200, "ok"));
+    let http_accessor = get_http_client_test(&mock).trace_propagation(HttpTracePropagation::W3CAndB3);
+    let parent = HttpTraceContext::from_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01", Some("vendor=1"))
+        .ok_or(String::from("do http_trace_propagation fail, parse traceparent"))?;
+    parent.clone().scope(http_accessor.get("http://trace.local/")).await
+        .map_err(|_| String::from("do http_trace_propagation fail"))?;
+    http_accessor.get("http://trace.local/").await
+        .map_err(|_| String::from("do http_trace_propagation fail"))?;
+    let spawned_accessor = http_accessor.clone();
+    let spawned_parent = parent.clone();
+    tokio::spawn(async move {
+        spawned_accessor.execute(httpaccessor::HttpAccessorRequest::get("http://trace.local/").trace_parent(spawned_parent)).await
+    }).await
+        .map_err(|_| String::from("do http_trace_propagation fail, spawn"))?
+        .map_err(|_| String::from("do http_trace_propagation fail, explicit parent"))?;
+    let requests = mock.requests();
+    let scoped = HttpTraceContext::from_headers(requests[0].headers())
+        .ok_or(String::from("do http_trace_propagation fail, no traceparent"))?;
+    let b3 = HttpTraceContext::from_b3(requests[0].headers())
+        .ok_or(String::from("do http_trace_propagation fail, no b3"))?;
+    let root = HttpTraceContext::from_headers(requests[1].headers())
+        .ok_or(String::from("do http_trace_propagation fail, no root traceparent"))?;
+    let explicit = HttpTraceContext::from_headers(requests[2].headers())
+        .ok_or(String::from("do http_trace_propagation fail, no explicit traceparent"))?;
+    match scoped.trace_id == parent.trace_id && scoped.span_id != parent.span_id
+        && requests[0].header_value("tracestate") == Some("vendor=1")
+        && b3.trace_id == parent.trace_id && b3.parent_span_id == Some(parent.span_id.clone())
+        && root.trace_id != parent.trace_id
+        && explicit.trace_id == parent.trace_id && explicit.span_id != parent.span_id {
+        true => Ok(()),
+        false => Err(String::from("do http_trace_propagation fail, wrong context"))
+    }
+}
+
+struct TestSubscriber {
+    next_id: std::sync::atomic::AtomicU64
+}
+
+impl tracing::Subscriber for TestSubscriber {
+    fn enabled(&self, _metadata: &tracing::Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, _span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+        tracing::span::Id::from_u64(self.next_id.fetch_add(1, std::sync::atomic::Ordering::SeqCst))
+    }
+
+    fn record(&self, _span: &tracing::span::Id, _values: &tracing::span::Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &tracing::span::Id, _follows: &tracing::span::Id) {}
+
+    fn event(&self, _event: &tracing::Event<'_>) {}
+
+    fn enter(&self, _span: &tracing::span::Id) {}
+
+    fn exit(&self, _span: &tracing::span::Id) {}
+}
+
+#[tokio::test]
+async fn test_http_trace_span_propagation() -> Result<(), String> {
+    use tracing::Instrument;
+    let _subscriber = tracing::subscriber::set_default(HttpTraceSubscriber::new(TestSubscriber {
+        next_id: std::sync::atomic::AtomicU64::new(1)
+    }));
+    let mock = HttpMockTransport::new()
+        .route(HttpMockRoute::get("http://trace.local/").respond(200, "ok"));
+    let http_accessor = get_http_client_test(&mock).trace_propagation(HttpTracePropagation::W3C);
+    let parent = HttpTraceContext::from_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01", None)
+        .ok_or(String::from("do http_trace_span_propagation fail, parse traceparent"))?;
+    let incoming = tracing::info_span!("incoming");
+    parent.attach(&incoming);
+    let nested_accessor = http_accessor.clone();
+    async move {
+        nested_accessor.get("http://trace.local/").instrument(tracing::info_span!("nested")).await?;
+        let spawned_accessor = nested_accessor.clone();
+        tokio::spawn(async move {
+            spawned_accessor.get("http://trace.local/").await
+        }.instrument(tracing::Span::current())).await.unwrap()
+    }.instrument(incoming).await
+        .map_err(|_| String::from("do http_trace_span_propagation fail, request in span"))?;
+    let unattached_accessor = http_accessor.clone();
+    async move {
+        unattached_accessor.get("http://trace.local/").await?;
+        unattached_accessor.get("http://trace.local/").await
+    }.instrument(tracing::info_span!("batch")).await
+        .map_err(|_| String::from("do http_trace_span_propagation fail, request in unattached span"))?;
+    let trace_ids: Vec<String> = mock.requests().iter()
+        .filter_map(|request| HttpTraceContext::from_headers(request.headers()))
+        .map(|trace_context| trace_context.trace_id)
+        .collect();
+    match trace_ids.as_slice() {
+        [nested, spawned, first, second] if *nested == parent.trace_id && *spawned == parent.trace_id
+            && first == second && *first != parent.trace_id => Ok(()),
+        _ => Err(String::from("do http_trace_span_propagation fail, wrong context"))
+    }
+}
+
+#[tokio::test]
+async fn test_http_rate_limiter() -> Result<(), String> {
+    let mock = HttpMockTransport::new()
+        .route(HttpMockRoute::get("http://limited.local/").respond(200, "ok"))
+        .route(HttpMockRoute::get("http://adaptive.local/").times(1).respond(200, "ok")
+            .header("X-RateLimit-Remaining", "0").header("X-RateLimit-Reset", "1"))
+        .route(HttpMockRoute::get("http://adaptive.local/").respond(200, "ok"));
+    let http_accessor = get_http_client_test(&mock)
+        .rate_limiter(HttpRateLimiter::new().global(10.0, 1).adaptive(true));
+    let start = std::time::Instant::now();
+    let resp_list = http_accessor.multi_get(&["http://limited.local/"; 4]).await
+        .map_err(|_| String::from("do http_rate_limiter fail"))?;
+    if resp_list.iter().any(|resp| resp.is_err()) || start.elapsed() < std::time::Duration::from_millis(280) {
+        return Err(String::from("do http_rate_limiter fail, global limit not applied"));
+    }
+    let start = std::time::Instant::now();
+    for _ in 0..2 {
+        http_accessor.get("http://adaptive.local/").await.map_err(|_| String::from("do http_rate_limiter fail"))?;
+    }
+    match start.elapsed() >= std::time::Duration::from_millis(900) {
+        true => Ok(()),
+        false => Err(String::from("do http_rate_limiter fail, rate limit headers ignored"))
+    }
+}
+
+#[tokio::test]
+async fn test_http_shared_rate_limiter() -> Result<(), String> {
+    let base_url = format!("{}/", test_server::spawn_http_server(200, "ok").await);
+    let rate_limiter = HttpRateLimiter::new().global(10.0, 1);
+    let http_accessor = httpaccessor::HttpAccessor::new()
+        .timeout(5)
+        .rate_limiter(rate_limiter.clone());
+    let cloned_accessor = http_accessor.clone();
+    let other_accessor = httpaccessor::HttpAccessor::new()
+        .timeout(5)
+        .rate_limiter(rate_limiter);
+    let start = std::time::Instant::now();
+    let single_rst = http_accessor.get(base_url.as_str()).await;
+    let multi_rst = cloned_accessor.multi_get(&[base_url.as_str(); 2]).await;
+    let other_rst = other_accessor.get(base_url.as_str()).await;
+    let elapsed = start.elapsed();
+    let multi_ok = multi_rst.map(|resp_list| resp_list.iter().all(|resp| resp.is_ok())).unwrap_or(false);
+    match single_rst.is_ok() && multi_ok && other_rst.is_ok() && elapsed >= std::time::Duration::from_millis(280) {
+        true => Ok(()),
+        false => Err(String::from("do http_shared_rate_limiter fail"))
+    }
+}
+
+#[tokio::test]
+async fn test_http_body_compression() -> Result<(), String> {
+    use std::io::Read;
+    let mock = HttpMockTransport::new()
+        .route(HttpMockRoute::post("http://ingest.local/").respond(204, ""));
+    let http_accessor = get_http_client_test(&mock).compression(HttpCompression::Gzip, 64);
+    let large_body = serde_json::json!({"rows": vec!["payload"; 64]}).to_string();
+    http_accessor.post("http://ingest.local/", "{}").await.map_err(|_| String::from("do http_body_compression fail"))?;
+    http_accessor.post("http://ingest.local/", large_body.as_str()).await.map_err(|_| String::from("do http_body_compression fail"))?;
+    let zstd_request = httpaccessor::HttpAccessorRequest::post("http://ingest.local/", "{}").compression(HttpCompression::Zstd);
+    http_accessor.execute(zstd_request).await.map_err(|_| String::from("do http_body_compression fail"))?;
+    let requests = mock.requests();
+    if requests[0].header_value("content-encoding").is_some() || requests[0].body_bytes() != Some(&b"{}"[..]) {
+        return Err(String::from("do http_body_compression fail, small body compressed"));
+    }
+    let mut gunzipped = String::new();
+    flate2::read::GzDecoder::new(requests[1].body_bytes().unwrap_or(&[]))
+        .read_to_string(&mut gunzipped)
+        .map_err(|_| String::from("do http_body_compression fail, invalid gzip"))?;
+    let unzstd = zstd::stream::decode_all(requests[2].body_bytes().unwrap_or(&[]))
+        .map_err(|_| String::from("do http_body_compression fail, invalid zstd"))?;
+    match requests[1].header_value("content-encoding") == Some("gzip") && gunzipped == large_body
+        && requests[2].header_value("content-encoding") == Some("zstd") && unzstd == b"{}" {
+        true => Ok(()),
+        false => Err(String::from("do http_body_compression fail, wrong encoding"))
+    }
+}
+
+#[cfg(all(unix, feature="http_async"))]
+#[tokio::test]
+async fn test_http_unix_socket() -> Result<(), String> {
+    let socket_path = std::env::temp_dir().join(format!("rustcommon_http_{}.sock", std::process::id()));
+    let socket_path = socket_path.to_str().unwrap();
+    test_server::spawn_unix_http_server(socket_path, 200, "{\"ok\": true}").await;
+    let http_accessor = httpaccessor::HttpAccessor::new()
+        .timeout(5)
+        .unix_socket(socket_path);
+    let resp = http_accessor.get("http://localhost/v1.41/info").await;
+    let missing = httpaccessor::HttpAccessor::new()
+        .unix_socket("/nonexistent/rustcommon.sock")
+        .get("/info").await;
+    let _ = std::fs::remove_file(socket_path);
+    match resp {
+        Ok(resp) if resp.status_code() == 200 && resp.content() == "{\"ok\": true}" && missing.is_err() => Ok(()),
+        _ => Err(String::from("do http_unix_socket fail"))
+    }
+}
+
+#[tokio::test]
+async fn test_http_protocol() -> Result<(), String> {
+    let h2_base_url = test_server::spawn_h2_http_server("h2").await;
+    let h1_base_url = test_server::spawn_http_server(200, "h1").await;
+    let h2_resp = httpaccessor::HttpAccessor::new()
+        .timeout(5)
+        .http_protocol(HttpProtocol::Http2PriorKnowledge)
+        .http2_keep_alive(10, 5, true)
+        .get(format!("{}/", h2_base_url).as_str()).await
+        .map_err(|_| String::from("do http_protocol fail, h2 request"))?;
+    let h2c_resp = httpaccessor::HttpAccessor::new()
+        .timeout(5)
+        .http_protocol(HttpProtocol::Http2PriorKnowledge)
+        .get(format!("{}/", h2_base_url).as_str()).await
+        .map_err(|_| String::from("do http_protocol fail, h2c request"))?;
+    let redirect_base_url = test_server::spawn_redirect_http_server(format!("{}/", h1_base_url)).await;
+    let h1_resp = httpaccessor::HttpAccessor::new()
+        .timeout(5)
+        .http_protocol(HttpProtocol::Http1Only)
+        .get(format!("{}/", redirect_base_url).as_str()).await
+        .map_err(|_| String::from("do http_protocol fail, h1 request"))?;
+    match h2_resp.version() == reqwest::Version::HTTP_2 && h2_resp.content() == "h2"
+        && h2c_resp.version() == reqwest::Version::HTTP_2 && h2c_resp.content() == "h2"
+        && h1_resp.version() == reqwest::Version::HTTP_11 && h1_resp.status_code() == 200 && h1_resp.content() == "h1" {
+        true => Ok(()),
+        false => Err(String::from("do http_protocol fail, wrong version"))
+    }
+}
+
+#[tokio::test]
+async fn test_http2_connection_reuse() -> Result<(), String> {
+    let (base_url, connections) = test_server::spawn_counting_h2_http_server("h2").await;
+    let http_accessor = httpaccessor::HttpAccessor::new()
+        .timeout(5)
+        .http_protocol(HttpProtocol::Http2PriorKnowledge)
+        .http2_keep_alive(10, 5, true);
+    let cloned_accessor = http_accessor.clone();
+    for accessor in [&http_accessor, &cloned_accessor].iter() {
+        let resp = accessor.get(format!("{}/", base_url).as_str()).await
+            .map_err(|_| String::from("do http2_connection_reuse fail, request"))?;
+        if resp.version() != reqwest::Version::HTTP_2 {
+            return Err(String::from("do http2_connection_reuse fail, wrong version"));
+        }
+    }
+    match connections.load(std::sync::atomic::Ordering::SeqCst) {
+        1 => Ok(()),
+        _ => Err(String::from("do http2_connection_reuse fail, connection not shared"))
+    }
+}
+
+struct CountingResolver {
+    calls: std::sync::Arc<std::sync::atomic::AtomicUsize>
+}
+
+impl HttpResolver for CountingResolver {
+    fn resolve(&self, _host: &str) -> futures::future::BoxFuture<'_, Result<Vec<std::net::IpAddr>, std::io::Error>> {
+        self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        Box::pin(async { Ok(vec![std::net::IpAddr::from([127, 0, 0, 1])]) })
+    }
+}
+
+#[tokio::test]
+async fn test_http_resolve_override() -> Result<(), String> {
+    let base_url = test_server::spawn_http_server(200, "canary").await;
+    let port = base_url.rsplit(':').next().unwrap_or("80").to_string();
+    let calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let http_accessor = httpaccessor::HttpAccessor::new()
+        .timeout(5)
+        .resolve("Canary.Example.com", std::net::IpAddr::from([127, 0, 0, 1]))
+        .resolver(HttpCachingResolver::new(CountingResolver { calls: calls.clone() }, 60));
+    let pinned = http_accessor.get(format!("http://canary.example.com:{}/", port).as_str()).await
+        .map_err(|_| String::from("do http_resolve_override fail, pinned host"))?;
+    for _ in 0..2 {
+        let resolved = http_accessor.get(format!("http://backend.internal:{}/", port).as_str()).await
+            .map_err(|_| String::from("do http_resolve_override fail, custom resolver"))?;
+        if resolved.content() != "canary" {
+            return Err(String::from("do http_resolve_override fail, wrong content"));
+        }
+    }
+    match pinned.content() == "canary" && pinned.version() == reqwest::Version::HTTP_11
+        && calls.load(std::sync::atomic::Ordering::SeqCst) == 1 {
+        true => Ok(()),
+        false => Err(String::from("do http_resolve_override fail, resolver not cached"))
+    }
+}
+
+#[tokio::test]
+async fn test_http_resolver_transport_reuse() -> Result<(), String> {
+    let (base_url, connections) = test_server::spawn_counting_h2_http_server("resolved").await;
+    let port = base_url.rsplit(':').next().unwrap_or("80").to_string();
+    let calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let http_accessor = httpaccessor::HttpAccessor::new()
+        .timeout(5)
+        .http_protocol(HttpProtocol::Http2PriorKnowledge)
+        .resolver(CountingResolver { calls: calls.clone() });
+    for _ in 0..3 {
+        let resp = http_accessor.get(format!("http://backend.internal:{}/", port).as_str()).await
+            .map_err(|_| String::from("do http_resolver_transport_reuse fail, request"))?;
+        if resp.content() != "resolved" {
+            return Err(String::from("do http_resolver_transport_reuse fail, wrong content"));
+        }
+    }
+    match (calls.load(std::sync::atomic::Ordering::SeqCst), connections.load(std::sync::atomic::Ordering::SeqCst)) {
+        (1, 1) => Ok(()),
+        _ => Err(String::from("do http_resolver_transport_reuse fail, transport rebuilt"))
+    }
+}
+
+#[tokio::test]
+async fn test_http_retry_policy() -> Result<(), String> {
+    let mock = HttpMockTransport::new()
+        .route(HttpMockRoute::post("/transfer").times(1).respond(503, ""))
+        .route(HttpMockRoute::post("/transfer").respond(200, "done"))
+        .route(HttpMockRoute::get("/balance").times(1).respond(503, ""))
+        .route(HttpMockRoute::get("/balance").respond(200, "42"))
+        .route(HttpMockRoute::post("/connect").times(1).error(HttpTransportErrorKind::Connect))
+        .route(HttpMockRoute::post("/connect").respond(200, "connected"));
+    let http_accessor = get_http_client_test(&mock).retries(1).retry_interval(1);
+    let post_resp = http_accessor.post("http://api.local/transfer", "amount=1").await
+        .map_err(|_| String::from("do http_retry_policy fail, post"))?;
+    if post_resp.status_code() != 503 {
+        return Err(String::from("do http_retry_policy fail, post retried by default"));
+    }
+    let connect_resp = http_accessor.post("http://api.local/connect", "").await
+        .map_err(|_| String::from("do http_retry_policy fail, connect error not retried"))?;
+    let opt_in_resp = http_accessor.execute(httpaccessor::HttpAccessorRequest::post("http://api.local/transfer", "amount=1").retryable(true)).await
+        .map_err(|_| String::from("do http_retry_policy fail, opt in"))?;
+    let get_resp = http_accessor.clone().retry_idempotent(true).get("http://api.local/balance").await
+        .map_err(|_| String::from("do http_retry_policy fail, get"))?;
+    match (connect_resp.content(), opt_in_resp.status_code(), get_resp.content(), mock.requests().len()) {
+        ("connected", 200, "42", 6) => Ok(()),
+        _ => Err(String::from("do http_retry_policy fail"))
     }
 }
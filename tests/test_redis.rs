@@ -2,16 +2,13 @@ mod test_env;
 
 use rustcommon::redisaccessor_async;
 
-use tokio;
-use log::kv::Source;
-
-fn get_redis_client_test<'a>() -> redisaccessor_async::RedisAccessorAsync<'a> {
+fn get_redis_client_test() -> redisaccessor_async::RedisAccessorAsync {
     let get_default = || redisaccessor_async::RedisAccessorAsync::new()
         .host("localhost")
         .port(6379)
         .passwd("")
         .db(0);
-    let ref env_map = test_env::ENV_CONFIG;
+    let env_map = &test_env::ENV_CONFIG;
     if env_map.contains_key("redis.host") &&
         env_map.contains_key("redis.port") &&
         env_map.contains_key("redis.passwd") &&
@@ -25,10 +22,21 @@ fn get_redis_client_test<'a>() -> redisaccessor_async::RedisAccessorAsync<'a> {
     get_default()
 }
 
+#[test]
+fn test_redis_owned_config() {
+    fn assert_static_send_sync<T: Send + Sync + 'static>(_: &T) {}
+    let host = String::from("localhost");
+    let redisaccessor = redisaccessor_async::RedisAccessorAsync::new()
+        .host(host.as_str())
+        .passwd(host.as_str());
+    drop(host);
+    assert_static_send_sync(&redisaccessor);
+}
+
 #[tokio::test]
 async fn test_redis_async_multi_set() -> Result<(), String> {
     let mut redisaccessor = get_redis_client_test();
-    let _ = redisaccessor.open_connection().await.unwrap();
+    redisaccessor.open_connection().await.unwrap();
     let dataset = vec![("test1".to_string(), "jjj".to_string(), 300), ("test2".to_string(), "kkk".to_string(), 300)];
     let rst = redisaccessor.multi_setex(dataset).await;
     match rst {
@@ -40,7 +48,7 @@ async fn test_redis_async_multi_set() -> Result<(), String> {
 #[tokio::test]
 async fn test_redis_async_multi_setex_expire() -> Result<(), String> {
     let mut redisaccessor = get_redis_client_test();
-    let _ = redisaccessor.open_connection().await.unwrap();
+    redisaccessor.open_connection().await.unwrap();
     let dataset = vec![("test1_nx".to_string(), "jjj2".to_string(), 300), ("test2_nx".to_string(), "kkk2".to_string(), 360)];
     let rst = redisaccessor.multi_setnx_expire(dataset).await;
     match rst {
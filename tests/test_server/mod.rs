@@ -0,0 +1,129 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+#[cfg(all(unix, feature="http_async"))]
+use tokio::net::UnixListener;
+
+async fn read_request<S: AsyncRead + Unpin>(stream: &mut S) -> Vec<u8> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        let n = match stream.read(&mut chunk).await {
+            Ok(0) | Err(_) => return buf,
+            Ok(n) => n
+        };
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(header_end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            let header = String::from_utf8_lossy(&buf[..header_end]).to_lowercase();
+            let content_length = header.lines()
+                .filter_map(|line| line.strip_prefix("content-length:"))
+                .filter_map(|val| val.trim().parse::<usize>().ok())
+                .next()
+                .unwrap_or(0);
+            if buf.len() >= header_end + 4 + content_length {
+                return buf;
+            }
+        }
+    }
+}
+
+pub async fn spawn_http_server(status_code: u16, body: &'static str) -> String {
+    let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(_) => return
+            };
+            tokio::spawn(async move {
+                serve_connection(&mut stream, status_code, body).await;
+            });
+        }
+    });
+    format!("http://{}", addr)
+}
+
+async fn serve_connection<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S, status_code: u16, body: &'static str) {
+    let _ = read_request(stream).await;
+    let resp = format!(
+        "HTTP/1.1 {} X\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status_code, body.len(), body
+    );
+    let _ = stream.write_all(resp.as_bytes()).await;
+}
+
+pub async fn spawn_redirect_http_server(location: String) -> String {
+    let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(_) => return
+            };
+            let location = location.clone();
+            tokio::spawn(async move {
+                let _ = read_request(&mut stream).await;
+                let resp = format!("HTTP/1.1 302 Found\r\nLocation: {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", location);
+                let _ = stream.write_all(resp.as_bytes()).await;
+            });
+        }
+    });
+    format!("http://{}", addr)
+}
+
+#[cfg(all(unix, feature="http_async"))]
+pub async fn spawn_unix_http_server(socket_path: &str, status_code: u16, body: &'static str) {
+    let _ = std::fs::remove_file(socket_path);
+    let mut listener = UnixListener::bind(socket_path).unwrap();
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(_) => return
+            };
+            tokio::spawn(async move {
+                serve_connection(&mut stream, status_code, body).await;
+            });
+        }
+    });
+}
+
+pub async fn dead_base_url() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+    format!("http://{}", addr)
+}
+
+pub async fn spawn_h2_http_server(body: &'static str) -> String {
+    spawn_counting_h2_http_server(body).await.0
+}
+
+pub async fn spawn_counting_h2_http_server(body: &'static str) -> (String, Arc<AtomicUsize>) {
+    let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let connections = Arc::new(AtomicUsize::new(0));
+    let connections_clone = connections.clone();
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(_) => return
+            };
+            connections_clone.fetch_add(1, Ordering::SeqCst);
+            tokio::spawn(async move {
+                let service = hyper::service::service_fn(move |_req| async move {
+                    Ok::<_, hyper::Error>(hyper::Response::new(hyper::Body::from(body)))
+                });
+                let _ = hyper::server::conn::Http::new()
+                    .http2_only(true)
+                    .serve_connection(stream, service)
+                    .await;
+            });
+        }
+    });
+    (format!("http://{}", addr), connections)
+}
@@ -0,0 +1,111 @@
+use rustcommon::wsaccessor;
+
+use tokio::net::TcpListener;
+use futures::{SinkExt, StreamExt};
+use tokio_tungstenite::tungstenite::Message;
+
+async fn spawn_ws_echo_server(close_first_after: usize) -> String {
+    let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let mut conn_count = 0;
+        loop {
+            let (stream, _) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(_) => return
+            };
+            conn_count += 1;
+            let close_after = match conn_count {
+                1 => close_first_after,
+                _ => usize::MAX
+            };
+            tokio::spawn(async move {
+                let mut ws_stream = match tokio_tungstenite::accept_async(stream).await {
+                    Ok(ws_stream) => ws_stream,
+                    Err(_) => return
+                };
+                let mut echoed = 0;
+                while let Some(Ok(msg)) = ws_stream.next().await {
+                    if msg.is_text() || msg.is_binary() {
+                        let _ = ws_stream.send(msg).await;
+                        echoed += 1;
+                        if echoed >= close_after {
+                            let _ = ws_stream.send(Message::Close(None)).await;
+                            return;
+                        }
+                    }
+                }
+            });
+        }
+    });
+    format!("ws://{}", addr)
+}
+
+#[tokio::test]
+async fn test_ws_echo_json() -> Result<(), String> {
+    let url = spawn_ws_echo_server(usize::MAX).await;
+    let mut ws_accessor = wsaccessor::WsAccessor::new().url(url.as_str());
+    ws_accessor.open_connection().await.map_err(|_| String::from("do ws_echo_json open fail"))?;
+    ws_accessor.send_json(&serde_json::json!({"id": 1})).await.map_err(|_| String::from("do ws_echo_json send fail"))?;
+    ws_accessor.send_binary(vec![1, 2, 3]).await.map_err(|_| String::from("do ws_echo_json send fail"))?;
+    let val: Option<Result<serde_json::Value, _>> = ws_accessor.next_json().await;
+    if val.and_then(|val| val.ok()) != Some(serde_json::json!({"id": 1})) {
+        return Err(String::from("do ws_echo_json fail, wrong json"));
+    }
+    match ws_accessor.next_message().await {
+        Some(Ok(wsaccessor::WsMessage::Binary(data))) if data == vec![1, 2, 3] => Ok(()),
+        _ => Err(String::from("do ws_echo_json fail, wrong binary"))
+    }
+}
+
+#[tokio::test]
+async fn test_ws_reconnect() -> Result<(), String> {
+    let url = spawn_ws_echo_server(1).await;
+    let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let recorded = events.clone();
+    let mut ws_accessor = wsaccessor::WsAccessor::new()
+        .url(url.as_str())
+        .reconnect_interval(50)
+        .on_reconnect(move |event| {
+            let name = match event {
+                wsaccessor::WsReconnectEvent::Lost(_) => "lost",
+                wsaccessor::WsReconnectEvent::Reconnected(resubscribe) => {
+                    resubscribe.push(wsaccessor::WsMessage::Text(String::from("subscribe")));
+                    "reconnected"
+                },
+                wsaccessor::WsReconnectEvent::ReconnectFailed(_) => "failed"
+            };
+            recorded.lock().unwrap().push(name);
+        });
+    ws_accessor.open_connection().await.map_err(|_| String::from("do ws_reconnect open fail"))?;
+    ws_accessor.send_text("first").await.map_err(|_| String::from("do ws_reconnect send fail"))?;
+    let first = ws_accessor.next_message().await;
+    tokio::time::delay_for(tokio::time::Duration::from_millis(300)).await;
+    ws_accessor.send_text("second").await.map_err(|_| String::from("do ws_reconnect send fail"))?;
+    let resubscribed = ws_accessor.next_message().await;
+    let second = ws_accessor.next_message().await;
+    let events = events.lock().unwrap().clone();
+    match (first, resubscribed, second) {
+        (Some(Ok(first)), Some(Ok(resubscribed)), Some(Ok(second))) if first.as_text() == Some("first")
+            && resubscribed.as_text() == Some("subscribe") && second.as_text() == Some("second")
+            && events == vec!["lost", "reconnected"] => Ok(()),
+        _ => Err(String::from("do ws_reconnect fail"))
+    }
+}
+
+#[tokio::test]
+async fn test_ws_ping_disabled() -> Result<(), String> {
+    let url = spawn_ws_echo_server(usize::MAX).await;
+    let mut ws_accessor = wsaccessor::WsAccessor::new()
+        .url(url.as_str())
+        .ping_interval(0)
+        .reconnect(false);
+    ws_accessor.open_connection().await.map_err(|_| String::from("do ws_ping_disabled open fail"))?;
+    ws_accessor.send_text("no ping").await.map_err(|_| String::from("do ws_ping_disabled send fail"))?;
+    let echoed = tokio::time::timeout(tokio::time::Duration::from_secs(5), ws_accessor.next_message()).await
+        .map_err(|_| String::from("do ws_ping_disabled fail, timeout"))?;
+    match echoed {
+        Some(Ok(message)) if message.as_text() == Some("no ping") => Ok(()),
+        _ => Err(String::from("do ws_ping_disabled fail"))
+    }
+}